@@ -215,6 +215,9 @@ fn print_keyboard_event(event: KbEvent, seat_name: &str) {
                 modifiers, seat_name
             );
         }
+        KbEvent::LockStateChanged { locks } => {
+            println!("Locks changed to {:?} on seat '{}'.", locks, seat_name);
+        }
         KbEvent::Repeat { keysym, utf8, .. } => {
             println!("Key repetition {:x} on seat '{}'.", keysym, seat_name);
             if let Some(txt) = utf8 {
@@ -244,6 +244,14 @@ impl DataDevice {
         let inner = self.inner.lock().unwrap();
         f(inner.selection.as_ref())
     }
+
+    /// Access the underlying `wl_data_device`
+    ///
+    /// SCTK handles all the events of this object. You can send your own requests
+    /// on it, but the state tracked by this `DataDevice` will not reflect them.
+    pub fn inner(&self) -> &wl_data_device::WlDataDevice {
+        &self.device
+    }
 }
 
 impl Drop for DataDevice {
@@ -192,7 +192,7 @@ impl crate::environment::GlobalHandler<wl_data_device_manager::WlDataDeviceManag
 ///
 /// You need to implement this trait for your environment struct, by
 /// delegating it to its `DataDeviceHandler` field in order to get the
-/// associated methods on your [`Environment`](../environment/struct.environment.html).
+/// associated methods on your [`Environment`](../environment/struct.Environment.html).
 pub trait DataDeviceHandling {
     /// Set the global drag'n'drop callback
     ///
@@ -6,13 +6,17 @@ use std::{
 
 use wayland_client::protocol::wl_data_device_manager::DndAction;
 use wayland_client::protocol::wl_data_offer;
-use wayland_client::Main;
+use wayland_client::{DispatchData, Main};
+
+type ActionsCallback = dyn FnMut(DndAction, DndAction, DispatchData) + Send;
 
 struct Inner {
     mime_types: Vec<String>,
     actions: DndAction,
     current_action: DndAction,
     serial: u32,
+    negotiated: Option<(DndAction, DndAction)>,
+    actions_callback: Option<Box<ActionsCallback>>,
 }
 
 /// A data offer for receiving data though copy/paste or
@@ -29,14 +33,17 @@ impl DataOffer {
             actions: DndAction::None,
             current_action: DndAction::None,
             serial: 0,
+            negotiated: None,
+            actions_callback: None,
         }));
         let inner2 = inner.clone();
-        offer.quick_assign(move |_, event, _| {
+        offer.quick_assign(move |_, event, ddata| {
             use self::wl_data_offer::Event;
             let mut inner = inner2.lock().unwrap();
             match event {
                 Event::Offer { mime_type } => {
                     inner.mime_types.push(mime_type);
+                    return;
                 }
                 Event::SourceActions { source_actions } => {
                     inner.actions = DndAction::from_bits_truncate(source_actions);
@@ -46,6 +53,16 @@ impl DataOffer {
                 }
                 _ => unreachable!(),
             }
+            // notify the callback without holding the lock, so that it can use the offer
+            if let Some(mut cb) = inner.actions_callback.take() {
+                let (actions, current_action) = (inner.actions, inner.current_action);
+                drop(inner);
+                cb(actions, current_action, ddata);
+                let mut inner = inner2.lock().unwrap();
+                if inner.actions_callback.is_none() {
+                    inner.actions_callback = Some(cb);
+                }
+            }
         });
 
         DataOffer {
@@ -73,6 +90,27 @@ impl DataOffer {
         self.inner.lock().unwrap().current_action
     }
 
+    /// Set a callback to be notified when the actions of this offer change
+    ///
+    /// It is invoked with the available actions of the source and the action
+    /// currently selected by the compositor, whenever one of them changes while the
+    /// drag and drop is in progress. Use it to update the feedback you display, like
+    /// a copy or move cursor badge.
+    pub fn set_actions_callback<F>(&self, callback: F)
+    where
+        F: FnMut(DndAction, DndAction, DispatchData) + Send + 'static,
+    {
+        self.inner.lock().unwrap().actions_callback = Some(Box::new(callback));
+    }
+
+    /// Access the underlying `wl_data_offer`
+    ///
+    /// SCTK handles all the events of this object. You can send your own requests
+    /// on it, but actions set this way are not known to `negotiate`.
+    pub fn inner(&self) -> &wl_data_offer::WlDataOffer {
+        &self.offer
+    }
+
     /// Accept a mime type for receiving data through this offer
     pub fn accept(&self, mime_type: Option<String>) {
         let serial = self.inner.lock().unwrap().serial;
@@ -112,10 +150,25 @@ impl DataOffer {
     /// You need to provide the set of supported actions, as well as
     /// a single preferred action.
     pub fn set_actions(&self, supported: DndAction, preferred: DndAction) {
+        self.inner.lock().unwrap().negotiated = Some((supported, preferred));
         self.offer
             .set_actions(supported.to_raw(), preferred.to_raw());
     }
 
+    /// Notify the source and compositor of the dnd actions you accept, if they changed
+    ///
+    /// This is like `set_actions`, but the request is only sent if the actions differ
+    /// from the last ones you set on this offer. You can thus call it on every motion
+    /// of the dnd cursor.
+    pub fn negotiate(&self, supported: DndAction, preferred: DndAction) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.negotiated != Some((supported, preferred)) {
+            inner.negotiated = Some((supported, preferred));
+            self.offer
+                .set_actions(supported.to_raw(), preferred.to_raw());
+        }
+    }
+
     /// Notify that you are finished with this offer, and will no longer
     /// be using it
     ///
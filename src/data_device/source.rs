@@ -137,6 +137,14 @@ impl DataSource {
             source: source.detach(),
         }
     }
+
+    /// Access the underlying `wl_data_source`
+    ///
+    /// SCTK handles all the events of this object and forwards them to the callback
+    /// of this `DataSource`. You can send your own requests on it.
+    pub fn inner(&self) -> &wl_data_source::WlDataSource {
+        &self.source
+    }
 }
 
 /// A file descriptor that can only be written to
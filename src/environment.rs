@@ -32,11 +32,30 @@
 //! [`default_environment!`](../macro.default_environment.html) macro to quickly setup things and bring
 //! in all SCTK modules.
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    any::Any,
+    cell::{Ref, RefCell, RefMut},
+    collections::VecDeque,
+    ffi::OsString,
+    fmt, io,
+    ops::{Bound, RangeBounds},
+    os::unix::{io::IntoRawFd, net::UnixStream},
+    path::PathBuf,
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
+};
+
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+};
+
+use crate::lazy_global::LazyGlobal as LazyState;
 
 use wayland_client::{
     protocol::{wl_display, wl_registry},
-    Attached, DispatchData, GlobalEvent, GlobalManager, Interface, Proxy,
+    AnonymousObject, Attached, ConnectError, DispatchData, Display, EventQueue, GlobalError,
+    GlobalEvent, GlobalManager, Interface, Main, Proxy, RawEvent,
 };
 
 /*
@@ -55,6 +74,44 @@ pub trait GlobalHandler<I: Interface> {
     );
     /// Access the global if it was signaled
     fn get(&self) -> Option<Attached<I>>;
+    /// The global with given id was removed
+    ///
+    /// This is called for every removed global of this interface, the handler is
+    /// responsible for checking that `id` is the one of the global it manages.
+    ///
+    /// The default implementation does nothing.
+    fn removed(&mut self, id: u32, ddata: DispatchData) {
+        let _ = (id, ddata);
+    }
+    /// Whether the handler needs a second roundtrip to finish its initialization
+    ///
+    /// The environment is initialized by a first roundtrip, during which the globals are
+    /// advertized and given to their handlers. Handlers that need to receive the initial
+    /// events of the globals they bound, like the `format` events of `wl_shm`, need a
+    /// second roundtrip. It is only done if one of the handlers of the environment needs it.
+    ///
+    /// The default implementation returns `true`.
+    fn needs_roundtrip(&self) -> bool {
+        true
+    }
+    /// The status of the global, for debugging purposes
+    ///
+    /// This is what the `Debug` implementation generated by the
+    /// [`environment!`](../macro.environment.html) macro displays for this global.
+    ///
+    /// The default implementation derives it from `get()`, handlers for which `get()`
+    /// has side effects should override it.
+    fn status(&self) -> GlobalStatus
+    where
+        I: AsRef<Proxy<I>> + From<Proxy<I>>,
+    {
+        match self.get() {
+            Some(global) => GlobalStatus::Bound {
+                version: global.as_ref().version(),
+            },
+            None => GlobalStatus::Missing,
+        }
+    }
 }
 
 /// Required trait for implementing a handler for "multi" globals
@@ -69,14 +126,136 @@ pub trait MultiGlobalHandler<I: Interface> {
     );
     /// The instance with given id was removed
     fn removed(&mut self, id: u32, ddata: DispatchData);
+    /// The instance with given id was removed, along with the proxy that was bound for it
+    ///
+    /// `global` is the instance bound for `id` by the handler the global is routed to in the
+    /// [`environment!`](../macro.environment.html) macro, as found by its `get_all_with_ids()`.
+    /// It is `None` if that handler does not track the ids of its instances. The handler the
+    /// global is routed to is called first, and can still use or destroy the proxy, then the
+    /// observers are called with the same proxy.
+    ///
+    /// The default implementation forwards to `removed()`.
+    fn removed_instance(&mut self, id: u32, global: Option<Attached<I>>, ddata: DispatchData) {
+        let _ = global;
+        self.removed(id, ddata);
+    }
     /// Access all the currently existing instances
     fn get_all(&self) -> Vec<Attached<I>>;
+    /// Access all the currently existing instances along with their ids
+    ///
+    /// These are the ids given to `created()` and `removed()`, the numeric names of the
+    /// globals in the registry.
+    ///
+    /// The default implementation returns an empty list, handlers keeping track of the
+    /// ids of their globals should override it.
+    fn get_all_with_ids(&self) -> Vec<(u32, Attached<I>)> {
+        Vec::new()
+    }
+    /// Whether the handler needs a second roundtrip to finish its initialization
+    ///
+    /// See [`GlobalHandler::needs_roundtrip`](trait.GlobalHandler.html#method.needs_roundtrip).
+    ///
+    /// The default implementation returns `true`.
+    fn needs_roundtrip(&self) -> bool {
+        true
+    }
+}
+
+/// Required trait for implementing a handler for the globals no other handler is declared for
+///
+/// See the `fallback` argument of the [`environment!`](../macro.environment.html) macro.
+pub trait DynGlobalHandler {
+    /// A global with given id, interface and version was created
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        interface: &str,
+        version: u32,
+        ddata: DispatchData,
+    );
+    /// The global with given id and interface was removed
+    ///
+    /// The default implementation does nothing.
+    fn removed(&mut self, id: u32, interface: &str, ddata: DispatchData) {
+        let _ = (id, interface, ddata);
+    }
+}
+
+/// Sharing a handler with the rest of your program
+///
+/// Wrapping a handler in an `Rc<RefCell<_>>` lets you keep a handle to it outside of
+/// the environment. The handler is borrowed mutably while it processes a registry event
+/// and immutably while its globals are accessed, so you must not keep it borrowed while
+/// dispatching the event queue, and the handler itself must not access it from its
+/// `created()` or `removed()` methods, or these borrows will panic.
+impl<I: Interface, H: GlobalHandler<I>> GlobalHandler<I> for Rc<RefCell<H>> {
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        ddata: DispatchData,
+    ) {
+        self.borrow_mut().created(registry, id, version, ddata)
+    }
+    fn get(&self) -> Option<Attached<I>> {
+        self.borrow().get()
+    }
+    fn removed(&mut self, id: u32, ddata: DispatchData) {
+        self.borrow_mut().removed(id, ddata)
+    }
+    fn needs_roundtrip(&self) -> bool {
+        self.borrow().needs_roundtrip()
+    }
+    fn status(&self) -> GlobalStatus
+    where
+        I: AsRef<Proxy<I>> + From<Proxy<I>>,
+    {
+        self.borrow().status()
+    }
+}
+
+/// Sharing a handler with the rest of your program
+///
+/// The same borrow rules as for the [`GlobalHandler`](trait.GlobalHandler.html)
+/// implementation apply.
+impl<I: Interface, H: MultiGlobalHandler<I>> MultiGlobalHandler<I> for Rc<RefCell<H>> {
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        ddata: DispatchData,
+    ) {
+        self.borrow_mut().created(registry, id, version, ddata)
+    }
+    fn removed(&mut self, id: u32, ddata: DispatchData) {
+        self.borrow_mut().removed(id, ddata)
+    }
+    fn removed_instance(&mut self, id: u32, global: Option<Attached<I>>, ddata: DispatchData) {
+        self.borrow_mut().removed_instance(id, global, ddata)
+    }
+    fn get_all(&self) -> Vec<Attached<I>> {
+        self.borrow().get_all()
+    }
+    fn get_all_with_ids(&self) -> Vec<(u32, Attached<I>)> {
+        self.borrow().get_all_with_ids()
+    }
+    fn needs_roundtrip(&self) -> bool {
+        self.borrow().needs_roundtrip()
+    }
 }
 
 /*
  * General Environment<E>
  */
 
+type ReentrancyHook = dyn Fn(&ReentrancyError);
+type Deferred = Rc<RefCell<VecDeque<Box<dyn FnOnce()>>>>;
+type GlobalListenerCallback<E> = Box<dyn FnMut(&str, &RefCell<E>, DispatchData)>;
+type GlobalListeners<E> = Rc<RefCell<Vec<Weak<RefCell<GlobalListenerCallback<E>>>>>>;
+
 /// A Wayland Environment
 ///
 /// This struct is generated by the `environment!` macro, see module-level documentation
@@ -92,6 +271,12 @@ pub struct Environment<E> {
     /// registry. See `wayland-client` documentation for details.
     pub manager: GlobalManager,
     inner: Rc<RefCell<E>>,
+    dispatching: Rc<RefCell<Option<String>>>,
+    reentrancy_hook: Rc<RefCell<Option<Box<ReentrancyHook>>>>,
+    global_listeners: GlobalListeners<E>,
+    globals: Rc<RefCell<Vec<GlobalDescription>>>,
+    deferred: Deferred,
+    registry: Rc<RefCell<Option<Attached<wl_registry::WlRegistry>>>>,
 }
 
 impl<E: InnerEnv + 'static> Environment<E> {
@@ -105,19 +290,144 @@ impl<E: InnerEnv + 'static> Environment<E> {
     /// to initialize your `Environment` using the
     /// [`init_default_environment!`](../macro.init_default_environment.html) macro.
     ///
-    /// You will need to do two roundtrips of the event queue afterward to filly initialize the environment.
+    /// You will need to do a roundtrip of the event queue afterward to fully initialize the environment,
+    /// and a second one if [`needs_roundtrip`](#method.needs_roundtrip) then returns `true`.
     pub fn init(display: &Attached<wl_display::WlDisplay>, env: E) -> Environment<E> {
         let inner = Rc::new(RefCell::new(env));
+        let dispatching = Rc::new(RefCell::new(None));
+        let global_listeners: GlobalListeners<E> = Rc::new(RefCell::new(Vec::new()));
+        let globals: Rc<RefCell<Vec<GlobalDescription>>> = Rc::new(RefCell::new(Vec::new()));
+        let deferred: Deferred = Rc::new(RefCell::new(VecDeque::new()));
+        let registry = Rc::new(RefCell::new(None));
 
         let my_inner = inner.clone();
-        let my_cb = move |event, registry, ddata: DispatchData| {
-            let mut inner = my_inner.borrow_mut();
-            inner.process_event(event, registry, ddata);
+        let my_dispatching = dispatching.clone();
+        let my_listeners = global_listeners.clone();
+        let my_deferred = deferred.clone();
+        let my_registry = registry.clone();
+        let my_globals = globals.clone();
+        let my_cb = move |event,
+                          registry: Attached<wl_registry::WlRegistry>,
+                          mut ddata: DispatchData| {
+            if my_registry.borrow().is_none() {
+                *my_registry.borrow_mut() = Some(registry.clone());
+            }
+            let interface = match event {
+                GlobalEvent::New { ref interface, .. } => interface.clone(),
+                GlobalEvent::Removed { ref interface, .. } => interface.clone(),
+            };
+            {
+                let _handling = HandlingGuard::enter(&my_deferred);
+                let mut inner = match my_inner.try_borrow_mut() {
+                    Ok(inner) => inner,
+                    Err(_) => panic!(
+                        "[SCTK] A registry event for `{}` was dispatched from within `Environment::with_inner` \
+                         or `Environment::with_inner_ref`. \
+                         The environment is already borrowed, defer dispatching until it is done.",
+                        interface
+                    ),
+                };
+                // keep track of all advertized globals, handled or not
+                let events = track_global(&*inner, &mut my_globals.borrow_mut(), event);
+                for event in events {
+                    *my_dispatching.borrow_mut() = Some(interface.clone());
+                    inner.process_event(event, registry.clone(), ddata.reborrow());
+                    *my_dispatching.borrow_mut() = None;
+                }
+            }
+            run_deferred(&my_inner, &my_deferred);
+            // notify the listeners once the environment is no longer borrowed, collecting
+            // them first so that they can register new listeners
+            let listeners = {
+                let mut listeners = my_listeners.borrow_mut();
+                listeners.retain(|lst| lst.upgrade().is_some());
+                listeners.iter().flat_map(Weak::upgrade).collect::<Vec<_>>()
+            };
+            for listener in listeners {
+                (*listener.borrow_mut())(&interface, &my_inner, ddata.reborrow());
+            }
         };
 
         let manager = GlobalManager::new_with_cb(&display, my_cb);
 
-        Environment { manager, inner }
+        Environment {
+            manager,
+            inner,
+            dispatching,
+            reentrancy_hook: Rc::new(RefCell::new(None)),
+            global_listeners,
+            globals,
+            deferred,
+            registry,
+        }
+    }
+
+    /// Whether a second roundtrip is needed to finish the initialization of the environment
+    ///
+    /// After creating the environment with `init`, you need to do a first roundtrip of the
+    /// event queue so that the globals are given to their handlers. If this method then
+    /// returns `true`, some handlers need a second roundtrip to receive the initial events of
+    /// the globals they bound. See
+    /// [`GlobalHandler::needs_roundtrip`](trait.GlobalHandler.html#method.needs_roundtrip).
+    pub fn needs_roundtrip(&self) -> bool {
+        self.borrow_inner("needs_roundtrip", None).needs_roundtrip()
+    }
+
+    /// Insert a new listener for the instances of a "multi" global
+    ///
+    /// The provided closure will be invoked whenever an instance of this global is
+    /// created or removed, after its `MultiGlobalHandler` has processed the event. It
+    /// is not called from within a borrow of the environment, so it can freely access it.
+    ///
+    /// Note that if instances already exist when this callback is setup, it'll not be
+    /// invoked on them. For you to be notified of them as well, you need to first process
+    /// them manually by calling `.get_all_globals()`.
+    ///
+    /// The returned [`GlobalListener`](struct.GlobalListener.html) keeps your callback
+    /// alive, dropping it will disable it.
+    ///
+    /// ```no_run
+    /// # use smithay_client_toolkit::{default_environment, init_default_environment};
+    /// # use smithay_client_toolkit::environment::GlobalUpdate;
+    /// # use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+    /// # default_environment!(MyApp, desktop);
+    /// # let (env, _display, _queue) = init_default_environment!(MyApp, desktop).unwrap();
+    /// let _listener = env.listen::<WlOutput, _>(|output, update, _| match update {
+    ///     GlobalUpdate::Created => println!("New output #{}", output.as_ref().id()),
+    ///     GlobalUpdate::Removed => println!("Output #{} removed", output.as_ref().id()),
+    /// });
+    /// ```
+    pub fn listen<I, F>(&self, mut f: F) -> GlobalListener
+    where
+        I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+        E: MultiGlobalHandler<I>,
+        F: FnMut(Attached<I>, GlobalUpdate, DispatchData) + 'static,
+    {
+        let mut known = self.get_all_globals::<I>();
+        let cb: GlobalListenerCallback<E> = Box::new(move |interface, inner, mut ddata| {
+            if interface != I::NAME {
+                return;
+            }
+            let current = inner.borrow().get_all();
+            let (removed, kept) = known.drain(..).partition::<Vec<_>, _>(|old: &Attached<I>| {
+                !current.iter().any(|new| new.as_ref().equals(old.as_ref()))
+            });
+            let created = current
+                .iter()
+                .filter(|new| !kept.iter().any(|old| old.as_ref().equals(new.as_ref())))
+                .cloned()
+                .collect::<Vec<_>>();
+            known = current;
+            for global in removed {
+                f(global, GlobalUpdate::Removed, ddata.reborrow());
+            }
+            for global in created {
+                f(global, GlobalUpdate::Created, ddata.reborrow());
+            }
+        });
+        let cb = Rc::new(RefCell::new(cb));
+        self.global_listeners.borrow_mut().push(Rc::downgrade(&cb));
+        GlobalListener { _cb: cb }
     }
 }
 
@@ -133,7 +443,37 @@ impl<E> Environment<E> {
     where
         E: GlobalHandler<I>,
     {
-        self.inner.borrow().get()
+        match self.try_borrow_inner("get_global", Some(I::NAME)) {
+            Some(inner) => inner.get(),
+            None => None,
+        }
+    }
+
+    /// Access a "single" global along with its bound version
+    ///
+    /// This is similar to `get_global`, but also returns the version the global
+    /// was bound with, which may be lower than the one advertized by the server.
+    /// It lets you check whether newer requests are available, for example:
+    ///
+    /// ```no_run
+    /// # use smithay_client_toolkit::{default_environment, init_default_environment};
+    /// # use smithay_client_toolkit::reexports::client::protocol::wl_shm::WlShm;
+    /// # default_environment!(MyApp, desktop);
+    /// # let (env, _display, _queue) = init_default_environment!(MyApp, desktop).unwrap();
+    /// let has_v2 = env
+    ///     .get_global_with_version::<WlShm>()
+    ///     .map(|(_, version)| version >= 2)
+    ///     .unwrap_or(false);
+    /// ```
+    pub fn get_global_with_version<I>(&self) -> Option<(Attached<I>, u32)>
+    where
+        I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+        E: GlobalHandler<I>,
+    {
+        self.get_global::<I>().map(|global| {
+            let version = global.as_ref().version();
+            (global, version)
+        })
     }
 
     /// Access a "single" global or panic
@@ -144,12 +484,108 @@ impl<E> Environment<E> {
     where
         E: GlobalHandler<I>,
     {
-        match self.inner.borrow().get() {
+        match self.borrow_inner("require_global", Some(I::NAME)).get() {
             Some(g) => g,
             None => panic!("[SCTK] A missing global was required: {}", I::NAME),
         }
     }
 
+    /// Dispatch the event queue until a "single" global is available
+    ///
+    /// Some globals may be advertized by the server later than the others. This method
+    /// returns the global immediately if it is already available, otherwise it dispatches
+    /// `queue` until the registry signals it, or until `timeout` expires if one is given.
+    ///
+    /// `queue` must be the event queue the environment was initialized on. Like with
+    /// `EventQueue::dispatch`, `data` is given to the callbacks of the dispatched events and
+    /// `fallback` receives the orphan events.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use smithay_client_toolkit::{default_environment, init_default_environment};
+    /// # use smithay_client_toolkit::reexports::client::protocol::wl_shm::WlShm;
+    /// # default_environment!(MyApp, desktop);
+    /// # let (env, _display, mut queue) = init_default_environment!(MyApp, desktop).unwrap();
+    /// let shm = env
+    ///     .wait_for_global::<WlShm, _, _>(
+    ///         &mut queue,
+    ///         &mut (),
+    ///         |_, _, _| {},
+    ///         Some(Duration::from_secs(1)),
+    ///     )
+    ///     .expect("wl_shm was not advertized in time");
+    /// ```
+    pub fn wait_for_global<I: Interface, T: Any, F>(
+        &self,
+        queue: &mut EventQueue,
+        data: &mut T,
+        mut fallback: F,
+        timeout: Option<Duration>,
+    ) -> Result<Attached<I>, WaitError>
+    where
+        E: GlobalHandler<I>,
+        F: FnMut(RawEvent, Main<AnonymousObject>, DispatchData),
+    {
+        if let Some(global) = self.get_global::<I>() {
+            return Ok(global);
+        }
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            queue
+                .dispatch_pending(data, &mut fallback)
+                .map_err(|err| WaitError::from_io(queue, err))?;
+            if let Some(global) = self.get_global::<I>() {
+                return Ok(global);
+            }
+            match queue.display().flush() {
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(WaitError::from_io(queue, err)),
+                Ok(()) => {}
+            }
+            let poll_timeout = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(WaitError::TimedOut);
+                    }
+                    // round up so that we do not spin on sub-millisecond remainders
+                    let remaining = deadline - now;
+                    let millis = remaining.as_millis() + 1;
+                    std::cmp::min(millis, std::i32::MAX as u128) as i32
+                }
+                None => -1,
+            };
+            // `None` means events are already pending on the queue
+            let guard = match queue.prepare_read() {
+                Some(guard) => guard,
+                None => continue,
+            };
+            let mut fds = [PollFd::new(
+                queue.display().get_connection_fd(),
+                PollFlags::POLLIN | PollFlags::POLLERR,
+            )];
+            match poll(&mut fds, poll_timeout) {
+                Ok(0) => {
+                    guard.cancel();
+                    continue;
+                }
+                Ok(_) => {}
+                Err(nix::Error::Sys(Errno::EINTR)) => {
+                    guard.cancel();
+                    continue;
+                }
+                Err(err) => {
+                    guard.cancel();
+                    let errno = err.as_errno().unwrap_or(Errno::EIO);
+                    return Err(WaitError::Io(io::Error::from(errno)));
+                }
+            }
+            guard
+                .read_events()
+                .map_err(|err| WaitError::from_io(queue, err))?;
+        }
+    }
+
     /// Access all instances of a "multi" global
     ///
     /// This will return a `Vec` containing all currently existing instances of the
@@ -160,7 +596,154 @@ impl<E> Environment<E> {
     where
         E: MultiGlobalHandler<I>,
     {
-        self.inner.borrow().get_all()
+        match self.try_borrow_inner("get_all_globals", Some(I::NAME)) {
+            Some(inner) => inner.get_all(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Access all instances of a "multi" global along with their bound versions
+    ///
+    /// This is similar to `get_all_globals`, but also returns the version each
+    /// instance was bound with.
+    pub fn get_all_globals_with_version<I>(&self) -> Vec<(Attached<I>, u32)>
+    where
+        I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+        E: MultiGlobalHandler<I>,
+    {
+        self.get_all_globals::<I>()
+            .into_iter()
+            .map(|global| {
+                let version = global.as_ref().version();
+                (global, version)
+            })
+            .collect()
+    }
+
+    /// List all the globals currently advertized by the server
+    ///
+    /// This includes the globals that are not handled by this environment, which are
+    /// marked as such. The list is kept up to date as globals are created and removed.
+    ///
+    /// Unlike `GlobalManager::list`, this can be called from within global handlers.
+    ///
+    /// The globals the environment ignored are reported here as well, this lets a test
+    /// suite check that the compositor does not advertize a "single" global twice:
+    ///
+    /// ```no_run
+    /// # use smithay_client_toolkit::{default_environment, init_default_environment};
+    /// # default_environment!(MyApp, desktop);
+    /// # let (env, _display, _queue) = init_default_environment!(MyApp, desktop).unwrap();
+    /// for global in env.list_globals() {
+    ///     if let Some(first) = global.duplicate_of {
+    ///         panic!("{} advertized as both {} and {}", global.interface, first, global.name);
+    ///     }
+    /// }
+    /// ```
+    pub fn list_globals(&self) -> Vec<GlobalDescription> {
+        self.globals.borrow().clone()
+    }
+
+    /// Access the registry of the environment
+    ///
+    /// This lets you bind globals the environment does not handle yourself, without
+    /// creating another registry. See also [`manual_bind`](#method.manual_bind).
+    ///
+    /// The registry is only known once the server advertized a global, so this returns
+    /// `None` before the initial roundtrip of the event queue.
+    pub fn registry(&self) -> Option<Attached<wl_registry::WlRegistry>> {
+        self.registry.borrow().clone()
+    }
+
+    /// Bind a global the environment does not handle
+    ///
+    /// This binds the first advertized global with the interface `I`, with the version it
+    /// was advertized with capped to `max_version`. It fails with `GlobalError::Missing` if no
+    /// such global was advertized, and `GlobalError::VersionTooLow` if its version is lower
+    /// than `min_version`.
+    ///
+    /// Unlike `GlobalManager::instantiate_range`, this can be called from within global
+    /// handlers. Each call binds a new instance of the global, you need to keep it around.
+    pub fn manual_bind<I>(&self, min_version: u32, max_version: u32) -> Result<Main<I>, GlobalError>
+    where
+        I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+    {
+        let (name, version) = self
+            .globals
+            .borrow()
+            .iter()
+            .find(|global| global.interface == I::NAME)
+            .map(|global| (global.name, global.version))
+            .ok_or(GlobalError::Missing)?;
+        if version < min_version {
+            return Err(GlobalError::VersionTooLow(version));
+        }
+        // the registry is known as soon as a global was advertized
+        let registry = self.registry().ok_or(GlobalError::Missing)?;
+        Ok(registry.bind::<I>(std::cmp::min(version, max_version), name))
+    }
+
+    /// Access all instances of a "multi" global along with their ids
+    ///
+    /// This is similar to `get_all_globals`, but also returns the numeric name of each
+    /// instance in the registry. It is forwarded to the `get_all_with_ids()` method of the
+    /// appropriate `MultiGlobalHandler`.
+    pub fn get_all_globals_with_ids<I: Interface>(&self) -> Vec<(u32, Attached<I>)>
+    where
+        E: MultiGlobalHandler<I>,
+    {
+        match self.try_borrow_inner("get_all_globals_with_ids", Some(I::NAME)) {
+            Some(inner) => inner.get_all_with_ids(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Find the instance of a "multi" global with given numeric name in the registry
+    ///
+    /// Some protocols refer to globals by their name rather than by their proxy, this
+    /// lets you find the instance the environment bound for it. It relies on the
+    /// `get_all_with_ids()` method of the appropriate `MultiGlobalHandler`, and returns
+    /// `None` once the instance has been removed.
+    pub fn find_global_by_name<I: Interface>(&self, name: u32) -> Option<Attached<I>>
+    where
+        E: MultiGlobalHandler<I>,
+    {
+        self.get_all_globals_with_ids::<I>()
+            .into_iter()
+            .find(|&(id, _)| id == name)
+            .map(|(_, global)| global)
+    }
+
+    /// Access a "single" global, attached to another event queue
+    ///
+    /// This is similar to `get_global`, but the returned handle is attached to `queue`
+    /// instead of the queue the environment was initialized on. The objects created from
+    /// its requests, like the pools created by a `wl_shm`, will deliver their events to
+    /// `queue`.
+    ///
+    /// The events of the global itself are still delivered to the original event queue,
+    /// including the ones already queued.
+    pub fn get_global_on<I>(&self, queue: &EventQueue) -> Option<Attached<I>>
+    where
+        I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+        E: GlobalHandler<I>,
+    {
+        self.get_global::<I>()
+            .map(|global| global.as_ref().attach(queue.token()))
+    }
+
+    /// Access all instances of a "multi" global, attached to another event queue
+    ///
+    /// This is similar to `get_all_globals`, with the same semantics as `get_global_on`.
+    pub fn get_all_globals_on<I>(&self, queue: &EventQueue) -> Vec<Attached<I>>
+    where
+        I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+        E: MultiGlobalHandler<I>,
+    {
+        self.get_all_globals::<I>()
+            .into_iter()
+            .map(|global| global.as_ref().attach(queue.token()))
+            .collect()
     }
 
     /// Access the inner environment
@@ -170,93 +753,1113 @@ impl<E> Environment<E> {
     /// [`default_environment!`](../macro.default_environment.html) macro.
     ///
     /// This method returns the return value of your closure.
+    ///
+    /// This method panics if it is called from within a global handler, or from
+    /// within the closure of another `with_inner` or `with_inner_ref` call.
     pub fn with_inner<T, F: FnOnce(&mut E) -> T>(&self, f: F) -> T {
-        let mut inner = self.inner.borrow_mut();
-        f(&mut *inner)
+        let ret = f(&mut *self.borrow_inner_mut("with_inner", None));
+        run_deferred(&self.inner, &self.deferred);
+        ret
     }
-}
 
-impl<E> Clone for Environment<E> {
-    fn clone(&self) -> Environment<E> {
-        Environment {
-            manager: self.manager.clone(),
-            inner: self.inner.clone(),
+    /// Access the inner environment immutably
+    ///
+    /// This is similar to `with_inner`, but only gives you a shared reference to the
+    /// inner environment. As such, it can be nested in other `with_inner_ref` calls,
+    /// and the closure can itself use `get_global` or `get_all_globals`.
+    ///
+    /// This method panics if it is called from within a global handler, which accesses
+    /// the inner environment mutably, or from within the closure of a `with_inner` call.
+    pub fn with_inner_ref<T, F: FnOnce(&E) -> T>(&self, f: F) -> T {
+        let ret = f(&*self.borrow_inner("with_inner_ref", None));
+        run_deferred(&self.inner, &self.deferred);
+        ret
+    }
+
+    /// Run a closure once the environment is no longer borrowed
+    ///
+    /// The environment cannot be accessed while a global handler processes a registry
+    /// event, or from within the closures of `with_inner` and `with_inner_ref`. If you
+    /// need to access it from there, for example to create a surface when a seat is
+    /// advertized, you can defer that work with this method: `f` is run as soon as the
+    /// environment is released. If it is not currently borrowed, `f` is run immediately.
+    ///
+    /// The closures are run in the order they were deferred.
+    ///
+    /// The global handlers do not have access to the environment, they can use
+    /// [`defer_in_handler`](fn.defer_in_handler.html) instead.
+    ///
+    /// ```no_run
+    /// # use smithay_client_toolkit::{default_environment, init_default_environment};
+    /// # use smithay_client_toolkit::reexports::client::protocol::wl_compositor::WlCompositor;
+    /// # default_environment!(MyApp, desktop);
+    /// # let (env, _display, _queue) = init_default_environment!(MyApp, desktop).unwrap();
+    /// env.with_inner(|_inner| {
+    ///     let env2 = env.clone();
+    ///     // calling `env.get_global()` here would panic
+    ///     env.defer(move || {
+    ///         let compositor = env2.require_global::<WlCompositor>();
+    ///         let _surface = compositor.create_surface();
+    ///     });
+    /// });
+    /// ```
+    pub fn defer<F: FnOnce() + 'static>(&self, f: F) {
+        self.deferred.borrow_mut().push_back(Box::new(f));
+        run_deferred(&self.inner, &self.deferred);
+    }
+
+    /// Set a hook to be notified of reentrant accesses to the environment
+    ///
+    /// By default, calling `get_global` or `get_all_globals` (or their `_with_version` and
+    /// `_with_ids` variants) from within a global handler or a `with_inner` closure panics,
+    /// as the inner environment is already borrowed. If a hook is set, it is instead given
+    /// the [`ReentrancyError`](struct.ReentrancyError.html) and these methods respectively
+    /// return `None` and an empty `Vec`.
+    ///
+    /// The other methods have no value to fall back to, and still panic. To access the
+    /// environment from these places, use [`defer`](#method.defer).
+    pub fn set_reentrancy_hook<F: Fn(&ReentrancyError) + 'static>(&self, hook: F) {
+        *self.reentrancy_hook.borrow_mut() = Some(Box::new(hook));
+    }
+
+    fn reentrancy_error(
+        &self,
+        method: &'static str,
+        requested: Option<&'static str>,
+    ) -> ReentrancyError {
+        ReentrancyError {
+            method,
+            requested,
+            dispatching: self.dispatching.borrow().clone(),
         }
     }
-}
 
-/// Internal trait for the `Environment` logic
-///
-/// This trait is automatically implemented by the [`environment!`](../macro.environment.html)
-/// macro, you should not implement it manually unless you seriously want to.
-pub trait InnerEnv {
-    /// Process a `GlobalEvent`
-    fn process_event(
-        &mut self,
-        event: GlobalEvent,
-        registry: Attached<wl_registry::WlRegistry>,
-        data: DispatchData,
-    );
-}
+    fn borrow_inner(&self, method: &'static str, requested: Option<&'static str>) -> Ref<'_, E> {
+        match self.inner.try_borrow() {
+            Ok(inner) => inner,
+            Err(_) => panic!("{}", self.reentrancy_error(method, requested)),
+        }
+    }
 
-/*
- * Simple handlers
- */
+    fn borrow_inner_mut(
+        &self,
+        method: &'static str,
+        requested: Option<&'static str>,
+    ) -> RefMut<'_, E> {
+        match self.inner.try_borrow_mut() {
+            Ok(inner) => inner,
+            Err(_) => panic!("{}", self.reentrancy_error(method, requested)),
+        }
+    }
 
-/// A minimalist global handler for "single" globals
-///
-/// This handler will simply register the global as soon as the registry signals
-/// it, and do nothing more.
-///
-/// It is appropriate for globals that never generate events, like `wl_compositor`
-/// or `wl_data_device_manager`.
-pub struct SimpleGlobal<I: Interface> {
-    global: Option<Attached<I>>,
+    // Like `borrow_inner`, but give the error to the reentrancy hook if any rather than panicking
+    fn try_borrow_inner(
+        &self,
+        method: &'static str,
+        requested: Option<&'static str>,
+    ) -> Option<Ref<'_, E>> {
+        match self.inner.try_borrow() {
+            Ok(inner) => Some(inner),
+            Err(_) => {
+                let error = self.reentrancy_error(method, requested);
+                match *self.reentrancy_hook.borrow() {
+                    Some(ref hook) => {
+                        hook(&error);
+                        None
+                    }
+                    None => panic!("{}", error),
+                }
+            }
+        }
+    }
 }
 
-impl<I: Interface> SimpleGlobal<I> {
-    /// Create a new handler
-    pub fn new() -> SimpleGlobal<I> {
-        SimpleGlobal { global: None }
-    }
+thread_local! {
+    // The deferred queues of the environments whose handlers are processing a registry event
+    static HANDLING: RefCell<Vec<Deferred>> = RefCell::new(Vec::new());
 }
 
-impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> GlobalHandler<I> for SimpleGlobal<I> {
-    fn created(
-        &mut self,
-        registry: Attached<wl_registry::WlRegistry>,
-        id: u32,
-        version: u32,
-        _: DispatchData,
-    ) {
-        self.global = Some((*registry.bind::<I>(version, id)).clone())
-    }
-    fn get(&self) -> Option<Attached<I>> {
-        self.global.clone()
+// Marks the deferred queue of an environment as the one of the handlers being run
+struct HandlingGuard;
+
+impl HandlingGuard {
+    fn enter(deferred: &Deferred) -> HandlingGuard {
+        HANDLING.with(|handling| handling.borrow_mut().push(deferred.clone()));
+        HandlingGuard
     }
 }
 
-/*
- * environment! macro
- */
+impl Drop for HandlingGuard {
+    fn drop(&mut self) {
+        HANDLING.with(|handling| handling.borrow_mut().pop());
+    }
+}
 
-/// Macro for declaring an environment
-///
-/// It needs to be used in conjunction with a a `struct` you declared, which will serve as the inner
-/// environment and hold the handlers for your globals.
+/// Run a closure once the global handlers are done processing a registry event
 ///
-/// The macro is invoked as such:
+/// The global handlers are given `&mut self`, so the environment stays borrowed while they
+/// process a registry event, and they do not have access to it anyway. This function is the
+/// counterpart of [`Environment::defer`](struct.Environment.html#method.defer) for them: when
+/// called from the `created()` or `removed()` method of a handler, `f` is run once the
+/// environment dispatching the event is released. Anywhere else, `f` is run immediately.
 ///
 /// ```no_run
 /// # extern crate smithay_client_toolkit as sctk;
-/// # use sctk::reexports::client::protocol::{wl_compositor::WlCompositor, wl_subcompositor::WlSubcompositor, wl_output::WlOutput};
-/// # use sctk::environment::SimpleGlobal;
+/// # use std::{cell::RefCell, rc::Rc};
+/// # use sctk::reexports::client::protocol::{wl_compositor::WlCompositor, wl_registry::WlRegistry};
+/// # use sctk::reexports::client::{Attached, DispatchData, Display};
 /// # use sctk::environment;
-/// # use sctk::output::OutputHandler;
-/// struct MyEnv {
-///     compositor: SimpleGlobal<WlCompositor>,
-///     subcompositor: SimpleGlobal<WlSubcompositor>,
-///     outputs: OutputHandler
+/// # use sctk::environment::{DynGlobalHandler, Environment, SimpleGlobal};
+/// struct GlobalLogger {
+///     env: Rc<RefCell<Option<Environment<MyEnv>>>>,
+/// }
+///
+/// impl DynGlobalHandler for GlobalLogger {
+///     fn created(&mut self, _: Attached<WlRegistry>, _: u32, interface: &str, _: u32, _: DispatchData) {
+///         let env = self.env.clone();
+///         let interface = interface.to_owned();
+///         environment::defer_in_handler(move || {
+///             // the environment is no longer borrowed here
+///             if let Some(ref env) = *env.borrow() {
+///                 let count = env.list_globals().iter().filter(|g| g.interface == interface).count();
+///                 println!("{} instances of {} are advertized", count, interface);
+///             }
+///         });
+///     }
+/// }
+///
+/// struct MyEnv {
+///     compositor: SimpleGlobal<WlCompositor>,
+///     logger: GlobalLogger,
+/// }
+///
+/// environment!(MyEnv,
+///     singles = [WlCompositor => compositor],
+///     multis = [],
+///     fallback = logger,
+/// );
+///
+/// let display = Display::connect_to_env().unwrap();
+/// let mut queue = display.create_event_queue();
+/// let slot = Rc::new(RefCell::new(None));
+/// let env = Environment::init(
+///     &(*display).clone().attach(queue.token()),
+///     MyEnv {
+///         compositor: SimpleGlobal::new(),
+///         logger: GlobalLogger { env: slot.clone() },
+///     },
+/// );
+/// *slot.borrow_mut() = Some(env.clone());
+/// queue.sync_roundtrip(&mut (), |_, _, _| {}).unwrap();
+/// ```
+pub fn defer_in_handler<F: FnOnce() + 'static>(f: F) {
+    let current = HANDLING.with(|handling| handling.borrow().last().cloned());
+    match current {
+        Some(deferred) => deferred.borrow_mut().push_back(Box::new(f)),
+        None => f(),
+    }
+}
+
+// Run the deferred closures while the environment is not borrowed
+fn run_deferred<E>(inner: &RefCell<E>, deferred: &RefCell<VecDeque<Box<dyn FnOnce()>>>) {
+    while inner.try_borrow_mut().is_ok() {
+        // do not keep the queue borrowed while running the closure, it may defer more work
+        let f = match deferred.borrow_mut().pop_front() {
+            Some(f) => f,
+            None => break,
+        };
+        f();
+    }
+}
+
+// Record a registry event in the list of advertized globals, and return the events to give to
+// the handlers of the environment
+//
+// Globals advertized with a version lower than required, and duplicates of "single" globals,
+// are not given to the handlers.
+fn track_global<E: InnerEnv>(
+    inner: &E,
+    globals: &mut Vec<GlobalDescription>,
+    event: GlobalEvent,
+) -> Vec<GlobalEvent> {
+    let capped_version = |interface: &str, version: u32| match inner.version_range(interface) {
+        Some((_, max_version)) => std::cmp::min(version, max_version),
+        None => version,
+    };
+    match event {
+        GlobalEvent::New {
+            id,
+            interface,
+            version,
+        } => {
+            let required_version = match inner.version_range(&interface) {
+                Some((min_version, _)) if version < min_version => {
+                    log::warn!(
+                        "Ignoring {} version {}, version {} is required.",
+                        interface,
+                        version,
+                        min_version
+                    );
+                    Some(min_version)
+                }
+                _ => None,
+            };
+            // a "single" global can only be given once to its handler
+            let duplicate_of = if required_version.is_none() && inner.handles_single(&interface) {
+                globals
+                    .iter()
+                    .find(|global| global.interface == interface && global.is_given())
+                    .map(|global| global.name)
+            } else {
+                None
+            };
+            if let Some(first) = duplicate_of {
+                log::warn!(
+                    "Ignoring {} with id {}, it was already advertized with id {}.",
+                    interface,
+                    id,
+                    first
+                );
+            }
+            let description = GlobalDescription {
+                name: id,
+                interface: interface.clone(),
+                version,
+                handled: inner.handles(&interface),
+                required_version,
+                duplicate_of,
+            };
+            let given = description.is_given();
+            globals.push(description);
+            if given {
+                let version = capped_version(&interface, version);
+                vec![GlobalEvent::New {
+                    id,
+                    interface,
+                    version,
+                }]
+            } else {
+                Vec::new()
+            }
+        }
+        GlobalEvent::Removed { id, interface } => {
+            let given = globals
+                .iter()
+                .any(|global| global.name == id && global.is_given());
+            globals.retain(|global| global.name != id);
+            if given {
+                let mut events = vec![GlobalEvent::Removed { id, interface }];
+                // give the next duplicate of this global to its handler instead
+                let next = globals
+                    .iter()
+                    .find(|global| global.duplicate_of == Some(id))
+                    .map(|global| global.name);
+                if let Some(next) = next {
+                    for global in globals.iter_mut() {
+                        if global.duplicate_of == Some(id) {
+                            global.duplicate_of = if global.name == next {
+                                None
+                            } else {
+                                Some(next)
+                            };
+                        }
+                    }
+                    let global = globals.iter().find(|global| global.name == next).unwrap();
+                    events.push(GlobalEvent::New {
+                        id: next,
+                        interface: global.interface.clone(),
+                        version: capped_version(&global.interface, global.version),
+                    });
+                }
+                events
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for Environment<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner.try_borrow() {
+            Ok(inner) => f.debug_tuple("Environment").field(&*inner).finish(),
+            Err(_) => f
+                .debug_tuple("Environment")
+                .field(&format_args!("<borrowed>"))
+                .finish(),
+        }
+    }
+}
+
+impl<E> Clone for Environment<E> {
+    fn clone(&self) -> Environment<E> {
+        Environment {
+            manager: self.manager.clone(),
+            inner: self.inner.clone(),
+            dispatching: self.dispatching.clone(),
+            reentrancy_hook: self.reentrancy_hook.clone(),
+            global_listeners: self.global_listeners.clone(),
+            globals: self.globals.clone(),
+            deferred: self.deferred.clone(),
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+/// The kind of change of an instance of a "multi" global
+///
+/// See [`Environment::listen`](struct.Environment.html#method.listen).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GlobalUpdate {
+    /// The instance was created
+    Created,
+    /// The instance was removed
+    Removed,
+}
+
+/// A handle to a global listener callback
+///
+/// Dropping it disables the associated callback and frees the closure.
+pub struct GlobalListener {
+    _cb: Rc<dyn Any>,
+}
+
+/// A reentrant access to the environment
+///
+/// The inner environment is borrowed while a global handler processes a registry
+/// event, and while the closure given to `Environment::with_inner` runs. Trying to
+/// access it again during that time is an error, which is described by this type.
+/// The same goes for mutable accesses while the closure given to
+/// `Environment::with_inner_ref` runs.
+///
+/// See [`Environment::set_reentrancy_hook`](struct.Environment.html#method.set_reentrancy_hook).
+#[derive(Clone, Debug)]
+pub struct ReentrancyError {
+    /// The method of `Environment` that was called
+    pub method: &'static str,
+    /// The interface of the global that was requested, if any
+    pub requested: Option<&'static str>,
+    /// The interface of the global whose registry event was being processed, if any
+    ///
+    /// If this is `None`, the environment was borrowed by `Environment::with_inner`
+    /// or `Environment::with_inner_ref`.
+    pub dispatching: Option<String>,
+}
+
+impl fmt::Display for ReentrancyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[SCTK] Environment::{}", self.method)?;
+        if let Some(requested) = self.requested {
+            write!(f, " for `{}`", requested)?;
+        }
+        match self.dispatching {
+            Some(ref interface) => write!(
+                f,
+                " was called while the handler of `{}` was processing a registry event.",
+                interface
+            )?,
+            None => write!(
+                f,
+                " was called from within `Environment::with_inner` or `Environment::with_inner_ref`."
+            )?,
+        }
+        write!(
+            f,
+            " The environment is already borrowed, defer this call until it is done."
+        )
+    }
+}
+
+impl std::error::Error for ReentrancyError {}
+
+/// Error returned by [`Environment::wait_for_global`](struct.Environment.html#method.wait_for_global)
+#[derive(Debug)]
+pub enum WaitError {
+    /// The global was not advertized before the timeout expired
+    TimedOut,
+    /// The connection to the server was lost
+    ConnectionLost(io::Error),
+    /// Another I/O error occurred while dispatching the event queue
+    Io(io::Error),
+}
+
+impl WaitError {
+    fn from_io(queue: &EventQueue, err: io::Error) -> WaitError {
+        let lost = queue.display().protocol_error().is_some()
+            || match err.kind() {
+                io::ErrorKind::BrokenPipe
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::UnexpectedEof => true,
+                _ => false,
+            };
+        if lost {
+            WaitError::ConnectionLost(err)
+        } else {
+            WaitError::Io(err)
+        }
+    }
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WaitError::TimedOut => f.write_str("timed out while waiting for a global"),
+            WaitError::ConnectionLost(ref err) => {
+                write!(f, "connection lost while waiting for a global: {}", err)
+            }
+            WaitError::Io(ref err) => write!(f, "I/O error while waiting for a global: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            WaitError::TimedOut => None,
+            WaitError::ConnectionLost(ref err) | WaitError::Io(ref err) => Some(err),
+        }
+    }
+}
+
+/// Internal trait for the `Environment` logic
+///
+/// This trait is automatically implemented by the [`environment!`](../macro.environment.html)
+/// macro, you should not implement it manually unless you seriously want to.
+pub trait InnerEnv {
+    /// Process a `GlobalEvent`
+    fn process_event(
+        &mut self,
+        event: GlobalEvent,
+        registry: Attached<wl_registry::WlRegistry>,
+        data: DispatchData,
+    );
+    /// Whether globals with this interface are routed to a handler
+    fn handles(&self, interface: &str) -> bool {
+        let _ = interface;
+        false
+    }
+    /// Whether globals with this interface are routed to a handler for "single" globals
+    fn handles_single(&self, interface: &str) -> bool {
+        let _ = interface;
+        false
+    }
+    /// The range of versions globals with this interface can be bound with, if restricted
+    ///
+    /// This is a `(min, max)` pair, both inclusive.
+    fn version_range(&self, interface: &str) -> Option<(u32, u32)> {
+        let _ = interface;
+        None
+    }
+    /// Whether one of the handlers needs a second roundtrip to finish its initialization
+    fn needs_roundtrip(&self) -> bool {
+        true
+    }
+}
+
+/// Description of a global advertized by the server
+///
+/// See [`Environment::list_globals`](struct.Environment.html#method.list_globals).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlobalDescription {
+    /// The numeric name of the global in the registry
+    pub name: u32,
+    /// The interface of the global
+    pub interface: String,
+    /// The version advertized by the server
+    pub version: u32,
+    /// Whether the environment routes this global to one of its handlers
+    ///
+    /// This does not account for the `fallback` handler of the environment, if any.
+    pub handled: bool,
+    /// The version required by the environment, if the advertized one is lower
+    ///
+    /// Such globals are not given to their handler.
+    pub required_version: Option<u32>,
+    /// The name of the global this one duplicates, if any
+    ///
+    /// "single" globals can only be given once to their handler: if another global with
+    /// the same interface was already advertized, this one is ignored, until the first one
+    /// is removed.
+    pub duplicate_of: Option<u32>,
+}
+
+impl GlobalDescription {
+    // Whether this global was given to the handler of the environment
+    fn is_given(&self) -> bool {
+        self.required_version.is_none() && self.duplicate_of.is_none()
+    }
+}
+
+/// Status of a "single" global
+///
+/// See [`GlobalHandler::status`](trait.GlobalHandler.html#method.status).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GlobalStatus {
+    /// The global was not advertized by the server
+    Missing,
+    /// The global was advertized but is not bound yet
+    Advertized {
+        /// The version the global will be bound with
+        version: u32,
+    },
+    /// The global is bound
+    Bound {
+        /// The version the global was bound with
+        version: u32,
+    },
+}
+
+/*
+ * Connection
+ */
+
+/// How to connect to the Wayland compositor
+///
+/// This is used by [`init_default_environment!`](../macro.init_default_environment.html) via
+/// its `connect` argument, and lets you target a specific compositor instance (like a nested
+/// compositor in tests) rather than whatever the environment variables point to.
+pub enum ConnectOptions {
+    /// Follow `WAYLAND_SOCKET` or `WAYLAND_DISPLAY`, like `Display::connect_to_env()`
+    Env,
+    /// Connect to the socket with given name in `XDG_RUNTIME_DIR`
+    Name(OsString),
+    /// Connect to the socket at this absolute path
+    Path(PathBuf),
+    /// Use an already connected socket, for example one received from a portal
+    Socket(UnixStream),
+}
+
+impl ConnectOptions {
+    /// Connect to the compositor
+    ///
+    /// The errors are the ones of `wayland-client`: `XdgRuntimeDirNotSet` if a socket name
+    /// was given but `XDG_RUNTIME_DIR` is not set, `InvalidName` if the provided path is not
+    /// absolute, and `NoCompositorListening` if the socket could not be connected to.
+    pub fn connect(self) -> Result<Display, ConnectError> {
+        match self {
+            ConnectOptions::Env => Display::connect_to_env(),
+            ConnectOptions::Name(name) => Display::connect_to_name(name),
+            ConnectOptions::Path(path) => {
+                if !path.is_absolute() {
+                    return Err(ConnectError::InvalidName);
+                }
+                let socket =
+                    UnixStream::connect(path).map_err(|_| ConnectError::NoCompositorListening)?;
+                ConnectOptions::Socket(socket).connect()
+            }
+            // The fd comes from a UnixStream, so it is a unix socket as required by `from_fd`
+            ConnectOptions::Socket(socket) => unsafe { Display::from_fd(socket.into_raw_fd()) },
+        }
+    }
+}
+
+/*
+ * Simple handlers
+ */
+
+/// A minimalist global handler for "single" globals
+///
+/// This handler will simply register the global as soon as the registry signals
+/// it, and do nothing more.
+///
+/// It is appropriate for globals that never generate events, like `wl_compositor`
+/// or `wl_data_device_manager`.
+///
+/// The global is bound with the version advertized by the server, capped to the
+/// highest version known to the protocol bindings, or to the range given to
+/// [`with_version_range`](#method.with_version_range).
+pub struct SimpleGlobal<I: Interface> {
+    global: Option<(u32, Attached<I>)>,
+    min_version: u32,
+    max_version: u32,
+}
+
+impl<I: Interface> SimpleGlobal<I> {
+    /// Create a new handler
+    pub fn new() -> SimpleGlobal<I> {
+        SimpleGlobal::with_version_range(1, I::VERSION)
+    }
+
+    /// Create a new handler binding at most the given version
+    pub fn with_max_version(max_version: u32) -> SimpleGlobal<I> {
+        SimpleGlobal::with_version_range(1, max_version)
+    }
+
+    /// Create a new handler binding a version within the given range
+    ///
+    /// If the server advertizes a version lower than `min_version`, the global is
+    /// not bound and `get()` returns `None`, as if it was missing.
+    pub fn with_version_range(min_version: u32, max_version: u32) -> SimpleGlobal<I> {
+        SimpleGlobal {
+            global: None,
+            min_version,
+            max_version,
+        }
+    }
+}
+
+impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> GlobalHandler<I> for SimpleGlobal<I> {
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        _: DispatchData,
+    ) {
+        if version < self.min_version {
+            log::warn!(
+                "Ignoring {} version {}, version {} is required.",
+                I::NAME,
+                version,
+                self.min_version
+            );
+            return;
+        }
+        let version = std::cmp::min(version, self.max_version);
+        self.global = Some((id, (*registry.bind::<I>(version, id)).clone()))
+    }
+    fn get(&self) -> Option<Attached<I>> {
+        self.global.as_ref().map(|(_, global)| global.clone())
+    }
+    fn removed(&mut self, id: u32, _: DispatchData) {
+        if self
+            .global
+            .as_ref()
+            .map(|&(global_id, _)| global_id == id)
+            .unwrap_or(false)
+        {
+            self.global = None;
+        }
+    }
+    fn needs_roundtrip(&self) -> bool {
+        // the global generates no event that could be waited for
+        false
+    }
+}
+
+/// A global handler for "single" globals binding them on first use
+///
+/// This handler records the global when the registry signals it, but only binds it the
+/// first time it is accessed with `get()`. Afterwards it behaves like a
+/// [`SimpleGlobal`](struct.SimpleGlobal.html).
+///
+/// It is appropriate for event-less globals that your app seldom uses, to avoid creating
+/// their objects on the server when they are not needed.
+pub struct LazyGlobal<I: Interface> {
+    registry: Option<Attached<wl_registry::WlRegistry>>,
+    id: Option<u32>,
+    global: RefCell<LazyState<I>>,
+    min_version: u32,
+    max_version: u32,
+}
+
+impl<I: Interface> LazyGlobal<I> {
+    /// Create a new handler
+    pub fn new() -> LazyGlobal<I> {
+        LazyGlobal::with_version_range(1, I::VERSION)
+    }
+
+    /// Create a new handler binding at most the given version
+    pub fn with_max_version(max_version: u32) -> LazyGlobal<I> {
+        LazyGlobal::with_version_range(1, max_version)
+    }
+
+    /// Create a new handler binding a version within the given range
+    ///
+    /// If the server advertizes a version lower than `min_version`, the global is
+    /// ignored and `get()` returns `None`, as if it was missing.
+    pub fn with_version_range(min_version: u32, max_version: u32) -> LazyGlobal<I> {
+        LazyGlobal {
+            registry: None,
+            id: None,
+            global: RefCell::new(LazyState::Unknown),
+            min_version,
+            max_version,
+        }
+    }
+}
+
+impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> GlobalHandler<I> for LazyGlobal<I> {
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        _: DispatchData,
+    ) {
+        if version < self.min_version {
+            log::warn!(
+                "Ignoring {} version {}, version {} is required.",
+                I::NAME,
+                version,
+                self.min_version
+            );
+            return;
+        }
+        let version = std::cmp::min(version, self.max_version);
+        self.registry = Some(registry);
+        self.id = Some(id);
+        *self.global.borrow_mut() = LazyState::Seen { id, version };
+    }
+    fn get(&self) -> Option<Attached<I>> {
+        let mut global = self.global.borrow_mut();
+        match *global {
+            LazyState::Bound(ref global) => Some(global.clone()),
+            LazyState::Unknown => None,
+            LazyState::Seen { id, version } => {
+                // registry cannot be None if we have seen the global
+                let registry = self.registry.as_ref().unwrap();
+                let bound = (*registry.bind::<I>(version, id)).clone();
+                *global = LazyState::Bound(bound.clone());
+                Some(bound)
+            }
+        }
+    }
+    fn removed(&mut self, id: u32, _: DispatchData) {
+        if self.id == Some(id) {
+            self.id = None;
+            *self.global.borrow_mut() = LazyState::Unknown;
+        }
+    }
+    fn needs_roundtrip(&self) -> bool {
+        // the global is not bound yet
+        false
+    }
+    fn status(&self) -> GlobalStatus {
+        // do not bind the global just to display it
+        match *self.global.borrow() {
+            LazyState::Bound(ref global) => GlobalStatus::Bound {
+                version: global.as_ref().version(),
+            },
+            LazyState::Seen { version, .. } => GlobalStatus::Advertized { version },
+            LazyState::Unknown => GlobalStatus::Missing,
+        }
+    }
+}
+
+/// A minimalist global handler for "multi" globals
+///
+/// This handler will simply bind each instance of the global as soon as the registry
+/// signals it, and forget it when it is removed.
+///
+/// It is appropriate for "multi" globals that never generate events, or whose proxies
+/// you only need to pass to other requests. Like [`SimpleGlobal`](struct.SimpleGlobal.html),
+/// it caps the bound version to the highest version known to the protocol bindings, or to
+/// the range given to [`with_version_range`](#method.with_version_range).
+pub struct SimpleMultiGlobal<I: Interface> {
+    globals: Vec<(u32, Attached<I>)>,
+    min_version: u32,
+    max_version: u32,
+    destructor: Option<fn(&I)>,
+}
+
+impl<I: Interface> SimpleMultiGlobal<I> {
+    /// Create a new handler
+    pub fn new() -> SimpleMultiGlobal<I> {
+        SimpleMultiGlobal::with_version_range(1, I::VERSION)
+    }
+
+    /// Create a new handler binding at most the given version
+    pub fn with_max_version(max_version: u32) -> SimpleMultiGlobal<I> {
+        SimpleMultiGlobal::with_version_range(1, max_version)
+    }
+
+    /// Create a new handler binding a version within the given range
+    ///
+    /// Instances advertized with a version lower than `min_version` are ignored.
+    pub fn with_version_range(min_version: u32, max_version: u32) -> SimpleMultiGlobal<I> {
+        SimpleMultiGlobal {
+            globals: Vec::new(),
+            min_version,
+            max_version,
+            destructor: None,
+        }
+    }
+
+    /// Set the function used to destroy the instances of this global when they are removed
+    ///
+    /// If the interface has a destructor request, like `release` for `wl_output`, you
+    /// should call it here so that the server can free the associated resources.
+    pub fn with_destructor(mut self, destructor: fn(&I)) -> SimpleMultiGlobal<I> {
+        self.destructor = Some(destructor);
+        self
+    }
+}
+
+impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> MultiGlobalHandler<I>
+    for SimpleMultiGlobal<I>
+{
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        _: DispatchData,
+    ) {
+        if version < self.min_version {
+            log::warn!(
+                "Ignoring {} version {}, version {} is required.",
+                I::NAME,
+                version,
+                self.min_version
+            );
+            return;
+        }
+        let version = std::cmp::min(version, self.max_version);
+        self.globals
+            .push((id, (*registry.bind::<I>(version, id)).clone()));
+    }
+    fn removed(&mut self, id: u32, _: DispatchData) {
+        let destructor = self.destructor;
+        self.globals.retain(|(global_id, global)| {
+            if *global_id != id {
+                return true;
+            }
+            if let Some(destructor) = destructor {
+                destructor(global);
+            }
+            false
+        });
+    }
+    fn get_all(&self) -> Vec<Attached<I>> {
+        self.globals
+            .iter()
+            .map(|(_, global)| global.clone())
+            .collect()
+    }
+    fn get_all_with_ids(&self) -> Vec<(u32, Attached<I>)> {
+        self.globals.clone()
+    }
+    fn needs_roundtrip(&self) -> bool {
+        // the global generates no event that could be waited for
+        false
+    }
+}
+
+type CreateGlobal<I> = Box<dyn FnMut(Attached<wl_registry::WlRegistry>, u32, u32) -> Attached<I>>;
+type RemoveGlobal<I> = Box<dyn FnMut(u32, Attached<I>)>;
+
+/// A "single" global handler built from a closure
+///
+/// See [`global_filter`](fn.global_filter.html).
+pub struct GlobalFilter<I: Interface> {
+    global: Option<(u32, Attached<I>)>,
+    create: CreateGlobal<I>,
+}
+
+/// Create a handler for a "single" global from a closure
+///
+/// The closure is invoked with the registry, the id and the version of the global
+/// when it is advertized, and must bind it. The returned global is then provided by
+/// `get()`, until the global is removed.
+///
+/// This avoids declaring a dedicated type for one-off handlers:
+///
+/// ```no_run
+/// # extern crate smithay_client_toolkit as sctk;
+/// # use sctk::reexports::client::protocol::wl_shm::{self, WlShm};
+/// # use sctk::environment::{self as env, GlobalFilter};
+/// # use sctk::environment;
+/// struct MyEnv {
+///     shm: GlobalFilter<WlShm>,
+/// }
+///
+/// environment!(MyEnv, singles = [WlShm => shm], multis = []);
+///
+/// let my_env = MyEnv {
+///     shm: env::global_filter(|registry, id, version| {
+///         let shm = registry.bind::<WlShm>(version, id);
+///         shm.quick_assign(|_, event, _| {
+///             if let wl_shm::Event::Format { format } = event {
+///                 println!("Supported format: {:?}", format);
+///             }
+///         });
+///         (*shm).clone()
+///     }),
+/// };
+/// ```
+pub fn global_filter<I, F>(create: F) -> GlobalFilter<I>
+where
+    I: Interface,
+    F: FnMut(Attached<wl_registry::WlRegistry>, u32, u32) -> Attached<I> + 'static,
+{
+    GlobalFilter {
+        global: None,
+        create: Box::new(create),
+    }
+}
+
+impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> GlobalHandler<I> for GlobalFilter<I> {
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        _: DispatchData,
+    ) {
+        self.global = Some((id, (self.create)(registry, id, version)));
+    }
+    fn get(&self) -> Option<Attached<I>> {
+        self.global.as_ref().map(|(_, global)| global.clone())
+    }
+    fn removed(&mut self, id: u32, _: DispatchData) {
+        if self.global.as_ref().map(|&(global_id, _)| global_id) == Some(id) {
+            self.global = None;
+        }
+    }
+}
+
+/// A "multi" global handler built from closures
+///
+/// See [`multi_global_filter`](fn.multi_global_filter.html).
+pub struct MultiGlobalFilter<I: Interface> {
+    globals: Vec<(u32, Attached<I>)>,
+    create: CreateGlobal<I>,
+    remove: RemoveGlobal<I>,
+}
+
+/// Create a handler for a "multi" global from closures
+///
+/// The first closure is invoked with the registry, the id and the version of each instance
+/// of the global when it is advertized, and must bind it. The second one is invoked with
+/// the id and the proxy of an instance when it is removed, before it is forgotten.
+pub fn multi_global_filter<I, C, R>(create: C, remove: R) -> MultiGlobalFilter<I>
+where
+    I: Interface,
+    C: FnMut(Attached<wl_registry::WlRegistry>, u32, u32) -> Attached<I> + 'static,
+    R: FnMut(u32, Attached<I>) + 'static,
+{
+    MultiGlobalFilter {
+        globals: Vec::new(),
+        create: Box::new(create),
+        remove: Box::new(remove),
+    }
+}
+
+impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> MultiGlobalHandler<I>
+    for MultiGlobalFilter<I>
+{
+    fn created(
+        &mut self,
+        registry: Attached<wl_registry::WlRegistry>,
+        id: u32,
+        version: u32,
+        _: DispatchData,
+    ) {
+        let global = (self.create)(registry, id, version);
+        self.globals.push((id, global));
+    }
+    fn removed(&mut self, id: u32, _: DispatchData) {
+        if let Some(pos) = self
+            .globals
+            .iter()
+            .position(|&(global_id, _)| global_id == id)
+        {
+            let (_, global) = self.globals.remove(pos);
+            (self.remove)(id, global);
+        }
+    }
+    fn get_all(&self) -> Vec<Attached<I>> {
+        self.globals
+            .iter()
+            .map(|(_, global)| global.clone())
+            .collect()
+    }
+    fn get_all_with_ids(&self) -> Vec<(u32, Attached<I>)> {
+        self.globals.clone()
+    }
+}
+
+// Used by the `environment!` macro to notify the handler a "multi" global is routed to of its
+// removal, returning the removed instance so that it can be passed to the observers
+#[doc(hidden)]
+pub fn __removed_instance<I, H>(
+    handler: &mut H,
+    id: u32,
+    ddata: DispatchData,
+) -> Option<Box<dyn Any>>
+where
+    I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+    H: MultiGlobalHandler<I>,
+{
+    let global = handler
+        .get_all_with_ids()
+        .into_iter()
+        .find(|&(global_id, _)| global_id == id)
+        .map(|(_, global)| global);
+    let instance = global
+        .clone()
+        .map(|global| Box::new(global) as Box<dyn Any>);
+    handler.removed_instance(id, global, ddata);
+    instance
+}
+
+// Used by the `environment!` macro to notify an observer of the removal of a global, the
+// instance is only given if it has the interface the observer expects
+#[doc(hidden)]
+pub fn __removed_observer<I, H>(
+    handler: &mut H,
+    id: u32,
+    instance: &Option<Box<dyn Any>>,
+    ddata: DispatchData,
+) where
+    I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+    H: MultiGlobalHandler<I>,
+{
+    let global = instance
+        .as_ref()
+        .and_then(|instance| instance.downcast_ref::<Attached<I>>())
+        .cloned();
+    handler.removed_instance(id, global, ddata);
+}
+
+// Used by the `environment!` macro to turn the version requirements into inclusive bounds
+#[doc(hidden)]
+pub fn __version_range<R: RangeBounds<u32>>(range: R) -> (u32, u32) {
+    let min = match range.start_bound() {
+        Bound::Included(&min) => min,
+        Bound::Excluded(&min) => min.saturating_add(1),
+        Bound::Unbounded => 1,
+    };
+    let max = match range.end_bound() {
+        Bound::Included(&max) => max,
+        Bound::Excluded(&max) => max.saturating_sub(1),
+        Bound::Unbounded => std::u32::MAX,
+    };
+    (min, max)
+}
+
+// Used by `init_default_environment!` to give its type to the orphan events fallback
+#[doc(hidden)]
+pub fn __orphan_fallback<F>(fallback: F) -> F
+where
+    F: FnMut(RawEvent, Main<AnonymousObject>, DispatchData),
+{
+    fallback
+}
+
+/*
+ * environment! macro
+ */
+
+/// Macro for declaring an environment
+///
+/// It needs to be used in conjunction with a a `struct` you declared, which will serve as the inner
+/// environment and hold the handlers for your globals.
+///
+/// The macro is invoked as such:
+///
+/// ```no_run
+/// # extern crate smithay_client_toolkit as sctk;
+/// # use sctk::reexports::client::protocol::{wl_compositor::WlCompositor, wl_subcompositor::WlSubcompositor, wl_output::WlOutput};
+/// # use sctk::environment::SimpleGlobal;
+/// # use sctk::environment;
+/// # use sctk::output::OutputHandler;
+/// struct MyEnv {
+///     compositor: SimpleGlobal<WlCompositor>,
+///     subcompositor: SimpleGlobal<WlSubcompositor>,
+///     outputs: OutputHandler
 /// }
 ///
 /// environment!(MyEnv,
@@ -279,39 +1882,273 @@ impl<I: Interface + Clone + From<Proxy<I>> + AsRef<Proxy<I>>> GlobalHandler<I> f
 ///   appropriate `GlobalHandler` or `MultiGlobalHandler` trait
 ///
 /// It is possible to route several globals to the same field as long as it implements all the appropriate traits.
+///
+/// You can also restrict the versions a global can be bound with by following its field with `@` and a
+/// range of versions:
+///
+/// ```no_run
+/// # extern crate smithay_client_toolkit as sctk;
+/// # use sctk::reexports::client::protocol::{wl_compositor::WlCompositor, wl_seat::WlSeat};
+/// # use sctk::environment::SimpleGlobal;
+/// # use sctk::environment;
+/// # use sctk::seat::SeatHandler;
+/// struct MyEnv {
+///     compositor: SimpleGlobal<WlCompositor>,
+///     seats: SeatHandler,
+/// }
+///
+/// environment!(MyEnv,
+///     singles = [WlCompositor => compositor @ 4..=4],
+///     multis = [WlSeat => seats @ 5..]
+/// );
+/// ```
+///
+/// The version given to the handler is then capped to the upper bound of the range, and the globals
+/// advertized with a version lower than its lower bound are ignored. They are still listed by
+/// [`Environment::list_globals`](environment/struct.Environment.html#method.list_globals), with the version that was
+/// required. These requirements only apply to the environment given to `Environment::init`, not to the
+/// environments used as fields of another one.
+///
+/// Each global can only be routed to a single field, which is the one `Environment::get_global` and
+/// `Environment::get_all_globals` are forwarded to. If other fields need to be notified of a global as well,
+/// you can list them in the optional `observers` argument, with the same syntax:
+///
+/// ```no_run
+/// # extern crate smithay_client_toolkit as sctk;
+/// # use sctk::reexports::client::protocol::{wl_output::WlOutput, wl_registry::WlRegistry};
+/// # use sctk::reexports::client::{Attached, DispatchData};
+/// # use sctk::environment;
+/// # use sctk::environment::MultiGlobalHandler;
+/// # use sctk::output::OutputHandler;
+/// #[derive(Default)]
+/// struct OutputCounter {
+///     count: usize,
+/// }
+///
+/// impl MultiGlobalHandler<WlOutput> for OutputCounter {
+///     fn created(&mut self, _: Attached<WlRegistry>, _: u32, _: u32, _: DispatchData) {
+///         self.count += 1;
+///     }
+///     fn removed(&mut self, _: u32, _: DispatchData) {
+///         self.count -= 1;
+///     }
+///     fn get_all(&self) -> Vec<Attached<WlOutput>> {
+///         Vec::new()
+///     }
+/// }
+///
+/// struct MyEnv {
+///     outputs: OutputHandler,
+///     counter: OutputCounter,
+/// }
+///
+/// environment!(MyEnv,
+///     singles = [],
+///     multis = [WlOutput => outputs],
+///     observers = [WlOutput => counter],
+/// );
+/// ```
+///
+/// The observers must implement `MultiGlobalHandler` for the global, even if it is a "single" global. They
+/// are notified after the field the global is routed to, in the order they are listed. If they need to bind
+/// the global, each of them binds its own instance of it.
+///
+/// The globals that are neither routed to a field nor observed are ignored, unless you name a field
+/// implementing [`DynGlobalHandler`](environment/trait.DynGlobalHandler.html) in the optional `fallback`
+/// argument, which then receives them all:
+///
+/// ```no_run
+/// # extern crate smithay_client_toolkit as sctk;
+/// # use sctk::reexports::client::protocol::{wl_output::WlOutput, wl_registry::WlRegistry};
+/// # use sctk::reexports::client::{Attached, DispatchData};
+/// # use sctk::environment;
+/// # use sctk::environment::DynGlobalHandler;
+/// # use sctk::output::OutputHandler;
+/// struct GlobalLogger;
+///
+/// impl DynGlobalHandler for GlobalLogger {
+///     fn created(&mut self, _: Attached<WlRegistry>, id: u32, interface: &str, version: u32, _: DispatchData) {
+///         println!("Unhandled global #{}: {} v{}", id, interface, version);
+///     }
+/// }
+///
+/// struct MyEnv {
+///     outputs: OutputHandler,
+///     logger: GlobalLogger,
+/// }
+///
+/// environment!(MyEnv,
+///     singles = [],
+///     multis = [WlOutput => outputs],
+///     fallback = logger,
+/// );
+/// ```
+///
+/// These globals are still reported as not handled by
+/// [`Environment::list_globals`](environment/struct.Environment.html#method.list_globals).
+///
+/// With the optional `debug = true` argument, the macro also implements `Debug` for your struct,
+/// displaying the [`status`](environment/trait.GlobalHandler.html#method.status) of each "single"
+/// global and the number of instances of each "multi" global, instead of its fields.
+/// `Environment<E>` forwards its `Debug` implementation to it, which lets you check what was bound:
+///
+/// ```no_run
+/// # use smithay_client_toolkit::{default_environment, init_default_environment};
+/// # default_environment!(MyApp, desktop, debug = true);
+/// # let (env, _display, _queue) = init_default_environment!(MyApp, desktop).unwrap();
+/// println!("{:#?}", env);
+/// ```
+///
+/// As the macro implements these traits for the environment itself, a field can also be another environment
+/// declared with this macro. This lets a library declare the globals it needs, and an app build on top of it
+/// by routing these globals to the library environment:
+///
+/// ```no_run
+/// # extern crate smithay_client_toolkit as sctk;
+/// # use sctk::reexports::client::protocol::{wl_compositor::WlCompositor, wl_shm::WlShm, wl_output::WlOutput};
+/// # use sctk::environment::SimpleGlobal;
+/// # use sctk::environment;
+/// # use sctk::output::OutputHandler;
+/// // declared by the library
+/// struct LibEnv {
+///     compositor: SimpleGlobal<WlCompositor>,
+///     outputs: OutputHandler,
+/// }
+///
+/// environment!(LibEnv,
+///     singles = [WlCompositor => compositor],
+///     multis = [WlOutput => outputs]
+/// );
+///
+/// // declared by the app
+/// struct AppEnv {
+///     lib: LibEnv,
+///     shm: SimpleGlobal<WlShm>,
+/// }
+///
+/// environment!(AppEnv,
+///     singles = [
+///         WlCompositor => lib,
+///         WlShm => shm,
+///     ],
+///     multis = [WlOutput => lib]
+/// );
+/// ```
 #[macro_export]
 macro_rules! environment {
     ($env_name:ident,
-        singles = [$($sty:ty => $sname:ident),* $(,)?],
-        multis = [$($mty:ty => $mname:ident),* $(,)?]$(,)?
+        singles = [$($sty:ty => $sname:ident $(@ $srange:expr)?),* $(,)?],
+        multis = [$($mty:ty => $mname:ident $(@ $mrange:expr)?),* $(,)?]
+        $(, observers = [$($oty:ty => $oname:ident),* $(,)?])?
+        $(, fallback = $fbname:ident)?
+        $(, debug = $debug:tt)?
+        $(,)?
     ) => {
         impl $crate::environment::InnerEnv for $env_name {
+            #[allow(unused_mut)]
             fn process_event(
                 &mut self,
                 event: $crate::reexports::client::GlobalEvent,
                 registry: $crate::reexports::client::Attached<$crate::reexports::client::protocol::wl_registry::WlRegistry>,
-                ddata: $crate::reexports::client::DispatchData,
+                mut ddata: $crate::reexports::client::DispatchData,
             ) {
                 match event {
-                    $crate::reexports::client::GlobalEvent::New { id, interface, version } => match &interface[..] {
-                        $(
-                            <$sty as $crate::reexports::client::Interface>::NAME => $crate::environment::GlobalHandler::<$sty>::created(&mut self.$sname, registry, id, version, ddata),
-                        )*
-                        $(
-                            <$mty as $crate::reexports::client::Interface>::NAME => $crate::environment::MultiGlobalHandler::<$mty>::created(&mut self.$mname, registry, id, version, ddata),
-                        )*
-                        _ => { /* ignore unkown globals */ }
+                    $crate::reexports::client::GlobalEvent::New { id, interface, version } => {
+                        match &interface[..] {
+                            $(
+                                <$sty as $crate::reexports::client::Interface>::NAME => $crate::environment::GlobalHandler::<$sty>::created(&mut self.$sname, registry.clone(), id, version, ddata.reborrow()),
+                            )*
+                            $(
+                                <$mty as $crate::reexports::client::Interface>::NAME => $crate::environment::MultiGlobalHandler::<$mty>::created(&mut self.$mname, registry.clone(), id, version, ddata.reborrow()),
+                            )*
+                            _ => {
+                                $(
+                                    if !$crate::environment::InnerEnv::handles(self, &interface) {
+                                        $crate::environment::DynGlobalHandler::created(&mut self.$fbname, registry.clone(), id, &interface, version, ddata.reborrow());
+                                    }
+                                )?
+                            }
+                        }
+                        $($(
+                            if interface == <$oty as $crate::reexports::client::Interface>::NAME {
+                                $crate::environment::MultiGlobalHandler::<$oty>::created(&mut self.$oname, registry.clone(), id, version, ddata.reborrow());
+                            }
+                        )*)?
                     },
-                    $crate::reexports::client::GlobalEvent::Removed { id, interface } => match &interface[..] {
-                        $(
-                            <$mty as $crate::reexports::client::Interface>::NAME => $crate::environment::MultiGlobalHandler::<$mty>::removed(&mut self.$mname, id, ddata),
-                        )*
-                        _ => { /* ignore unknown globals */ }
+                    $crate::reexports::client::GlobalEvent::Removed { id, interface } => {
+                        let instance: Option<Box<dyn ::std::any::Any>> = match &interface[..] {
+                            $(
+                                <$sty as $crate::reexports::client::Interface>::NAME => {
+                                    $crate::environment::GlobalHandler::<$sty>::removed(&mut self.$sname, id, ddata.reborrow());
+                                    None
+                                }
+                            )*
+                            $(
+                                <$mty as $crate::reexports::client::Interface>::NAME => $crate::environment::__removed_instance::<$mty, _>(&mut self.$mname, id, ddata.reborrow()),
+                            )*
+                            _ => {
+                                $(
+                                    if !$crate::environment::InnerEnv::handles(self, &interface) {
+                                        $crate::environment::DynGlobalHandler::removed(&mut self.$fbname, id, &interface, ddata.reborrow());
+                                    }
+                                )?
+                                None
+                            }
+                        };
+                        let _ = &instance;
+                        $($(
+                            if interface == <$oty as $crate::reexports::client::Interface>::NAME {
+                                $crate::environment::__removed_observer::<$oty, _>(&mut self.$oname, id, &instance, ddata.reborrow());
+                            }
+                        )*)?
                     }
                 }
             }
+
+            fn handles(&self, interface: &str) -> bool {
+                let handled: &[&str] = &[
+                    $(<$sty as $crate::reexports::client::Interface>::NAME,)*
+                    $(<$mty as $crate::reexports::client::Interface>::NAME,)*
+                    $($(<$oty as $crate::reexports::client::Interface>::NAME,)*)?
+                ];
+                handled.contains(&interface)
+            }
+
+            fn handles_single(&self, interface: &str) -> bool {
+                let singles: &[&str] = &[
+                    $(<$sty as $crate::reexports::client::Interface>::NAME,)*
+                ];
+                singles.contains(&interface)
+            }
+
+            fn needs_roundtrip(&self) -> bool {
+                false
+                $(|| $crate::environment::GlobalHandler::<$sty>::needs_roundtrip(&self.$sname))*
+                $(|| $crate::environment::MultiGlobalHandler::<$mty>::needs_roundtrip(&self.$mname))*
+                $($(|| $crate::environment::MultiGlobalHandler::<$oty>::needs_roundtrip(&self.$oname))*)?
+            }
+
+            fn version_range(&self, interface: &str) -> Option<(u32, u32)> {
+                let _ = interface;
+                $($(
+                    if interface == <$sty as $crate::reexports::client::Interface>::NAME {
+                        return Some($crate::environment::__version_range($srange));
+                    }
+                )?)*
+                $($(
+                    if interface == <$mty as $crate::reexports::client::Interface>::NAME {
+                        return Some($crate::environment::__version_range($mrange));
+                    }
+                )?)*
+                None
+            }
         }
 
+        $crate::__environment_debug!([$($debug)?] $env_name,
+            singles = [$($sty => $sname),*],
+            multis = [$($mty => $mname),*]
+        );
+
         $(
             impl $crate::environment::GlobalHandler<$sty> for $env_name {
                 fn created(&mut self, registry: $crate::reexports::client::Attached<$crate::reexports::client::protocol::wl_registry::WlRegistry>, id: u32, version: u32, ddata: $crate::reexports::client::DispatchData) {
@@ -320,6 +2157,15 @@ macro_rules! environment {
                 fn get(&self) -> Option<$crate::reexports::client::Attached<$sty>> {
                     $crate::environment::GlobalHandler::<$sty>::get(&self.$sname)
                 }
+                fn removed(&mut self, id: u32, ddata: $crate::reexports::client::DispatchData) {
+                    $crate::environment::GlobalHandler::<$sty>::removed(&mut self.$sname, id, ddata)
+                }
+                fn needs_roundtrip(&self) -> bool {
+                    $crate::environment::GlobalHandler::<$sty>::needs_roundtrip(&self.$sname)
+                }
+                fn status(&self) -> $crate::environment::GlobalStatus {
+                    $crate::environment::GlobalHandler::<$sty>::status(&self.$sname)
+                }
             }
         )*
 
@@ -331,10 +2177,48 @@ macro_rules! environment {
                 fn removed(&mut self, id: u32, ddata: $crate::reexports::client::DispatchData) {
                     $crate::environment::MultiGlobalHandler::<$mty>::removed(&mut self.$mname, id, ddata)
                 }
+                fn removed_instance(&mut self, id: u32, global: Option<$crate::reexports::client::Attached<$mty>>, ddata: $crate::reexports::client::DispatchData) {
+                    $crate::environment::MultiGlobalHandler::<$mty>::removed_instance(&mut self.$mname, id, global, ddata)
+                }
                 fn get_all(&self) -> Vec<$crate::reexports::client::Attached<$mty>> {
                     $crate::environment::MultiGlobalHandler::<$mty>::get_all(&self.$mname)
                 }
+                fn get_all_with_ids(&self) -> Vec<(u32, $crate::reexports::client::Attached<$mty>)> {
+                    $crate::environment::MultiGlobalHandler::<$mty>::get_all_with_ids(&self.$mname)
+                }
+                fn needs_roundtrip(&self) -> bool {
+                    $crate::environment::MultiGlobalHandler::<$mty>::needs_roundtrip(&self.$mname)
+                }
             }
         )*
     };
 }
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __environment_debug {
+    ([true] $env_name:ident,
+        singles = [$($sty:ty => $sname:ident),*],
+        multis = [$($mty:ty => $mname:ident),*]
+    ) => {
+        impl ::std::fmt::Debug for $env_name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($env_name))
+                    $(
+                        .field(
+                            <$sty as $crate::reexports::client::Interface>::NAME,
+                            &$crate::environment::GlobalHandler::<$sty>::status(&self.$sname),
+                        )
+                    )*
+                    $(
+                        .field(
+                            <$mty as $crate::reexports::client::Interface>::NAME,
+                            &$crate::environment::MultiGlobalHandler::<$mty>::get_all(&self.$mname).len(),
+                        )
+                    )*
+                    .finish()
+            }
+        }
+    };
+    ([$($debug:tt)?] $($rest:tt)*) => {};
+}
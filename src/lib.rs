@@ -37,6 +37,42 @@ pub mod reexports {
     pub use wayland_protocols as protocols;
 }
 
+/// Re-exports of the main entry points of SCTK, for convenience
+///
+/// This gathers the environment macros and types, the window and the memory pools,
+/// which are what most apps start with. `ConceptFrame` and `WaylandSource` are only
+/// included with the `frames` and `calloop` cargo features respectively, the example
+/// below does not need them:
+///
+/// ```no_run
+/// use smithay_client_toolkit::prelude::*;
+/// use smithay_client_toolkit::reexports::client::*;
+///
+/// default_environment!(MyApp, desktop);
+///
+/// let (env, _display, _queue) = init_default_environment!(MyApp, desktop)
+///     .expect("Unable to connect to a Wayland compositor");
+/// let _surface = env.create_surface();
+/// let _pools: DoubleMemPool = env.create_double_pool(|_| {}).unwrap();
+/// ```
+///
+/// It only contains types, functions and macros, so that it can be glob-imported along
+/// with other modules, like `wayland-client` above, without bringing conflicting trait
+/// methods into scope.
+pub mod prelude {
+    pub use crate::environment::{Environment, SimpleGlobal};
+    pub use crate::output::{with_output_info, OutputInfo};
+    pub use crate::seat::{with_seat_data, SeatData};
+    pub use crate::shm::{DoubleMemPool, MemPool};
+    #[cfg(feature = "frames")]
+    pub use crate::window::ConceptFrame;
+    pub use crate::window::Window;
+    #[cfg(feature = "calloop")]
+    pub use crate::WaylandSource;
+    pub use crate::{default_environment, environment, init_default_environment};
+    pub use crate::{get_surface_outputs, get_surface_scale_factor};
+}
+
 pub mod data_device;
 pub mod environment;
 mod lazy_global;
@@ -45,6 +81,7 @@ pub mod primary_selection;
 pub mod seat;
 pub mod shell;
 pub mod shm;
+pub mod user_data;
 pub mod window;
 
 #[cfg(feature = "calloop")]
@@ -88,9 +125,9 @@ pub use surface::{get_surface_outputs, get_surface_scale_factor};
 ///   - `xdg_decoration_manager` as a [`SimpleGlobal`](environment/struct.SimpleGlobal.html)
 ///
 /// You can also add the `fields` argument to add additional fields to the generated struct, and
-/// the `singles` and `multis` arguments to route additional globals like with the
-/// [`environment!`](macro.environment.html) macro. These three fields are optional, but they must
-/// appear in this order, and after the optional preset
+/// the `singles`, `multis`, `observers`, `fallback` and `debug` arguments to route additional globals
+/// and implement `Debug` like with the [`environment!`](macro.environment.html) macro. These six fields
+/// are optional, but they must appear in this order, and after the optional preset
 ///
 /// ```no_run
 /// # use smithay_client_toolkit::default_environment;
@@ -105,24 +142,49 @@ pub use surface::{get_surface_outputs, get_surface_scale_factor};
 ///     ],
 ///     multis=[
 ///         // add some routing here
+///     ],
+///     observers=[
+///         // add some observers here
+///     ]
+/// );
+/// ```
+///
+/// The generated struct is public by default. You can give it attributes and another visibility
+/// before its name, `pub(self)` keeping it private, and the additional fields can have their own
+/// visibility as well:
+///
+/// ```no_run
+/// # use smithay_client_toolkit::default_environment;
+/// default_environment!(
+///     #[doc(hidden)]
+///     pub(crate) MyEnv,
+///     desktop,
+///     fields=[
+///         pub(crate) somefield: u32,
 ///     ]
 /// );
 /// ```
 macro_rules! default_environment {
-    ($env_name:ident, desktop
-        $(,fields = [$($fname:ident : $fty:ty),* $(,)?])?
-        $(,singles = [$($sty:ty => $sname: ident),* $(,)?])?
-        $(,multis = [$($mty:ty => $mname:ident),* $(,)?])?
+    ($(#[$attr:meta])* $env_name:ident $(, $($rest:tt)*)?) => {
+        $crate::default_environment!($(#[$attr])* pub $env_name $(, $($rest)*)?);
+    };
+    ($(#[$attr:meta])* $vis:vis $env_name:ident, desktop
+        $(,fields = [$($fvis:vis $fname:ident : $fty:ty),* $(,)?])?
+        $(,singles = [$($sty:ty => $sname:ident $(@ $srange:expr)?),* $(,)?])?
+        $(,multis = [$($mty:ty => $mname:ident $(@ $mrange:expr)?),* $(,)?])?
+        $(,observers = [$($oty:ty => $oname:ident),* $(,)?])?
+        $(,fallback = $fbname:ident)?
+        $(,debug = $debug:tt)?
         $(,)?
     ) => {
-        $crate::default_environment!($env_name,
+        $crate::default_environment!($(#[$attr])* $vis $env_name,
             fields=[
                 // shell
                 sctk_shell: $crate::shell::ShellHandler,
                 // decoration
                 sctk_decoration_mgr: $crate::environment::SimpleGlobal<$crate::reexports::protocols::unstable::xdg_decoration::v1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1>,
                 // others
-                $($($fname : $fty,)*)?
+                $($($fvis $fname : $fty,)*)?
             ],
             singles = [
                 // shell globals
@@ -132,9 +194,12 @@ macro_rules! default_environment {
                 // decoration
                 $crate::reexports::protocols::unstable::xdg_decoration::v1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1 => sctk_decoration_mgr,
                 // others
-                $($($sty => $sname,)*)?
+                $($($sty => $sname $(@ $srange)?,)*)?
             ],
-            multis = [ $($($mty => $mname,)*)?  ],
+            multis = [ $($($mty => $mname $(@ $mrange)?,)*)?  ],
+            observers = [ $($($oty => $oname,)*)? ],
+            $(fallback = $fbname,)?
+            $(debug = $debug,)?
         );
 
         // Shell utility
@@ -144,16 +209,20 @@ macro_rules! default_environment {
             }
         }
     };
-    ($env_name:ident
-        $(,fields = [$($fname:ident : $fty:ty),* $(,)?])?
-        $(,singles = [$($sty:ty => $sname:ident),* $(,)?])?
-        $(,multis = [$($mty:ty => $mname:ident),* $(,)?])?
+    ($(#[$attr:meta])* $vis:vis $env_name:ident
+        $(,fields = [$($fvis:vis $fname:ident : $fty:ty),* $(,)?])?
+        $(,singles = [$($sty:ty => $sname:ident $(@ $srange:expr)?),* $(,)?])?
+        $(,multis = [$($mty:ty => $mname:ident $(@ $mrange:expr)?),* $(,)?])?
+        $(,observers = [$($oty:ty => $oname:ident),* $(,)?])?
+        $(,fallback = $fbname:ident)?
+        $(,debug = $debug:tt)?
         $(,)?
     ) => {
         /*
          * Declare the type
          */
-        pub struct $env_name {
+        $(#[$attr])*
+        $vis struct $env_name {
             // SimpleGlobals
             sctk_compositor: $crate::environment::SimpleGlobal<$crate::reexports::client::protocol::wl_compositor::WlCompositor>,
             sctk_subcompositor: $crate::environment::SimpleGlobal<$crate::reexports::client::protocol::wl_subcompositor::WlSubcompositor>,
@@ -169,7 +238,7 @@ macro_rules! default_environment {
             sctk_primary_selection_manager: $crate::primary_selection::PrimarySelectionHandler,
             // user added
             $($(
-                $fname : $fty,
+                $fvis $fname : $fty,
             )*)?
         }
 
@@ -260,7 +329,7 @@ macro_rules! default_environment {
                 $crate::reexports::protocols::unstable::primary_selection::v1::client::zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1 => sctk_primary_selection_manager,
                 $crate::reexports::protocols::misc::gtk_primary_selection::client::gtk_primary_selection_device_manager::GtkPrimarySelectionDeviceManager => sctk_primary_selection_manager,
                 // user added
-                $($($sty => $sname),*)?
+                $($($sty => $sname $(@ $srange)?),*)?
             ],
             multis = [
                 // output globals
@@ -268,8 +337,11 @@ macro_rules! default_environment {
                 // seat globals
                 $crate::reexports::client::protocol::wl_seat::WlSeat => sctk_seats,
                 // user added
-                $($($mty => $mname),*)?
-            ]
+                $($($mty => $mname $(@ $mrange)?),*)?
+            ],
+            observers = [ $($($oty => $oname),*)? ],
+            $(fallback = $fbname,)?
+            $(debug = $debug,)?
         );
     };
 }
@@ -302,6 +374,20 @@ macro_rules! default_environment {
 /// ).expect("Unable to connect to the wayland compositor");
 /// ```
 ///
+/// By default the connection is made using the `WAYLAND_SOCKET` and `WAYLAND_DISPLAY`
+/// environment variables. You can target a specific compositor instead with the `connect`
+/// argument, providing a [`ConnectOptions`](environment/enum.ConnectOptions.html):
+///
+/// ```no_run
+/// # use smithay_client_toolkit::{default_environment, init_default_environment};
+/// # use smithay_client_toolkit::environment::ConnectOptions;
+/// # default_environment!(MyEnv, desktop);
+/// let (env, display, queue) = init_default_environment!(MyEnv,
+///     desktop,
+///     connect=ConnectOptions::Name("wayland-1".into()),
+/// ).expect("Unable to connect to the wayland compositor");
+/// ```
+///
 /// If you instead want the macro to use some pre-existing display and event queue, you can
 /// add the `with` argument providing them. In that case the macro will evaluate to
 /// a `Result<Environment, io::Error>`, forwarding to you any error that may have occured
@@ -322,25 +408,52 @@ macro_rules! default_environment {
 ///     ]
 /// ).expect("Initial roundtrip failed!");
 /// ```
+///
+/// The initial roundtrips panic if an event is received for an object that has no filter
+/// assigned. If you created such objects on the queue before initializing the environment,
+/// you can instead handle these events with the `fallback` argument, given last. It takes
+/// a closure with the same signature as the fallback of `EventQueue::dispatch`:
+///
+/// ```no_run
+/// # use smithay_client_toolkit::{default_environment, init_default_environment};
+/// # default_environment!(MyEnv, desktop);
+/// let (env, display, queue) = init_default_environment!(MyEnv,
+///     desktop,
+///     fallback=|event, object, _| {
+///         eprintln!("Orphan event {}@{}.{}", event.interface, object.as_ref().id(), event.name);
+///     },
+/// ).expect("Unable to connect to the wayland compositor");
+/// ```
+///
+/// This fallback is only used during the initialization, you still need to provide one to each
+/// dispatch of the event queue afterwards.
 macro_rules! init_default_environment {
+    // the fallback for orphan events, which are not expected unless one is given
+    (@fallback $fallback:expr) => { $fallback };
+    (@fallback) => { |_, _, _| unreachable!() };
     ($env_name:ident, desktop
         $(, with=($display:expr, $queue:expr))?
+        $(, connect=$connect:expr)?
         $(,fields = [$($fname:ident : $fval:expr),* $(,)?])?
+        $(, fallback=$fallback:expr)?
         $(,)?
     ) => {
         $crate::init_default_environment!($env_name,
             $(with=($display, $queue),)?
+            $(connect=$connect,)?
             fields = [
                 sctk_shell: $crate::shell::ShellHandler::new(),
                 sctk_decoration_mgr: $crate::environment::SimpleGlobal::new(),
                 $($(
                     $fname: $fval,
                 )*)?
-            ]
+            ],
+            $(fallback=$fallback,)?
         )
     };
     ($env_name:ident, with=($display:expr, $queue:expr)
         $(,fields = [$($fname:ident : $fval:expr),* $(,)?])?
+        $(, fallback=$fallback:expr)?
         $(,)?
     ) => {
         {
@@ -362,9 +475,16 @@ macro_rules! init_default_environment {
                 )*)?
             });
 
-            // two roundtrips to init the environment
-            let ret = $queue .sync_roundtrip(&mut (), |_, _, _| unreachable!());
-            let ret = ret.and_then(|_| $queue.sync_roundtrip(&mut (), |_, _, _| unreachable!()));
+            // one roundtrip to init the environment, and a second one if the handlers need it
+            let mut fallback = $crate::environment::__orphan_fallback(
+                $crate::init_default_environment!(@fallback $($fallback)?)
+            );
+            let ret = $queue .sync_roundtrip(&mut (), &mut fallback);
+            let ret = ret.and_then(|n| if env.needs_roundtrip() {
+                $queue.sync_roundtrip(&mut (), &mut fallback)
+            } else {
+                Ok(n)
+            });
 
             // Bind primary selection manager
             let _psm = env.get_primary_selection_manager();
@@ -373,15 +493,22 @@ macro_rules! init_default_environment {
         }
     };
     ($env_name:ident
+        $(, connect=$connect:expr)?
         $(,fields = [$($fname:ident : $fval:expr),* $(,)?])?
+        $(, fallback=$fallback:expr)?
         $(,)?
     ) => {
-        $crate::reexports::client::Display::connect_to_env().and_then(|display| {
+        {
+            let connect_opts = $crate::environment::ConnectOptions::Env;
+            $(let connect_opts = $connect;)?
+            connect_opts.connect()
+        }.and_then(|display| {
             let mut queue = display.create_event_queue();
             let ret = $crate::init_default_environment!(
                 $env_name,
                 with=(display, queue),
                 fields=[$($($fname: $fval),*)?],
+                $(fallback=$fallback,)?
             );
             match ret {
                 Ok(env) => Ok((env, display, queue)),
@@ -3,7 +3,7 @@
 //! This modules provides two main elements. The first is the
 //! [`OutputHandler`](struct.OutputHandler.html) type, which is a
 //! [`MultiGlobalHandler`](../environment/trait.MultiGlobalHandler.html) for
-//! use with the [`init_environment!`](../macro.init_environment.html) macro. It is automatically
+//! use with the [`environment!`](../macro.environment.html) macro. It is automatically
 //! included if you use the [`init_default_environment!`](../macro.init_default_environment.html).
 //!
 //! The second is the [`with_output_info`](fn.with_output_info.html) with allows you to
@@ -119,7 +119,7 @@ type OutputStatusCallback = dyn FnMut(WlOutput, &OutputInfo, DispatchData) + 'st
 /// A handler for `wl_output`
 ///
 /// This handler can be used for managing `wl_output` in the
-/// [`init_environment!`](../macro.init_environment.html) macro, and is automatically
+/// [`environment!`](../macro.environment.html) macro, and is automatically
 /// included in [`init_default_environment!`](../macro.init_default_environment.html).
 ///
 /// It aggregates the output information and makes it available via the
@@ -150,10 +150,12 @@ impl crate::environment::MultiGlobalHandler<WlOutput> for OutputHandler {
         // We currently support wl_output up to version 3
         let version = std::cmp::min(version, 3);
         let output = registry.bind::<WlOutput>(version, id);
+        let udata_map = crate::user_data::init_user_data_map(output.as_ref())
+            .expect("SCTK: newly created wl_output already has UserData");
         if version > 1 {
             // wl_output.done event was only added at version 2
             // In case of an old version 1, we just behave as if it was send at the start
-            output.as_ref().user_data().set_threadsafe(|| {
+            udata_map.insert_if_missing_threadsafe(|| {
                 Mutex::new(OutputData::Pending {
                     id,
                     events: vec![],
@@ -161,7 +163,7 @@ impl crate::environment::MultiGlobalHandler<WlOutput> for OutputHandler {
                 })
             });
         } else {
-            output.as_ref().user_data().set_threadsafe(|| {
+            udata_map.insert_if_missing_threadsafe(|| {
                 Mutex::new(OutputData::Ready {
                     info: OutputInfo::new(id),
                     callbacks: vec![],
@@ -188,6 +190,9 @@ impl crate::environment::MultiGlobalHandler<WlOutput> for OutputHandler {
     fn get_all(&self) -> Vec<Attached<WlOutput>> {
         self.outputs.iter().map(|(_, o)| o.clone()).collect()
     }
+    fn get_all_with_ids(&self) -> Vec<(u32, Attached<WlOutput>)> {
+        self.outputs.clone()
+    }
 }
 
 fn process_output_event(
@@ -196,10 +201,7 @@ fn process_output_event(
     mut ddata: DispatchData,
     listeners: &RefCell<Vec<rc::Weak<RefCell<OutputStatusCallback>>>>,
 ) {
-    let udata_mutex = output
-        .as_ref()
-        .user_data()
-        .get::<Mutex<OutputData>>()
+    let udata_mutex = crate::user_data::get::<_, Mutex<OutputData>>(output.as_ref())
         .expect("SCTK: wl_output has invalid UserData");
     let mut udata = udata_mutex.lock().unwrap();
     if let Event::Done = event {
@@ -246,10 +248,7 @@ fn make_obsolete(
     mut ddata: DispatchData,
     listeners: &RefCell<Vec<rc::Weak<RefCell<OutputStatusCallback>>>>,
 ) {
-    let udata_mutex = output
-        .as_ref()
-        .user_data()
-        .get::<Mutex<OutputData>>()
+    let udata_mutex = crate::user_data::get::<_, Mutex<OutputData>>(output.as_ref())
         .expect("SCTK: wl_output has invalid UserData");
     let mut udata = udata_mutex.lock().unwrap();
     let (id, mut callbacks) = match *udata {
@@ -373,7 +372,7 @@ fn notify_status_listeners(
 /// will be set to `true`. This handler will not automatically detroy the output by calling its
 /// `release` method, to avoid interfering with your logic.
 pub fn with_output_info<T, F: FnOnce(&OutputInfo) -> T>(output: &WlOutput, f: F) -> Option<T> {
-    if let Some(ref udata_mutex) = output.as_ref().user_data().get::<Mutex<OutputData>>() {
+    if let Some(ref udata_mutex) = crate::user_data::get::<_, Mutex<OutputData>>(output.as_ref()) {
         let udata = udata_mutex.lock().unwrap();
         match *udata {
             OutputData::Ready { ref info, .. } => Some(f(info)),
@@ -398,7 +397,7 @@ pub fn add_output_listener<F: Fn(WlOutput, &OutputInfo, DispatchData) + Send + S
 ) -> OutputListener {
     let arc = Arc::new(f) as Arc<_>;
 
-    if let Some(udata_mutex) = output.as_ref().user_data().get::<Mutex<OutputData>>() {
+    if let Some(udata_mutex) = crate::user_data::get::<_, Mutex<OutputData>>(output.as_ref()) {
         let mut udata = udata_mutex.lock().unwrap();
 
         match *udata {
@@ -466,7 +465,7 @@ impl<E: OutputHandling> crate::environment::Environment<E> {
     /// For you to be notified of them as well, you need to first process them manually by calling
     /// `.get_all_outputs()`.
     ///
-    /// The returned [`OutputStatusListener`](../output/struct.OutputStatusListener.hmtl) keeps your
+    /// The returned [`OutputStatusListener`](../output/struct.OutputStatusListener.html) keeps your
     /// callback alive, dropping it will disable it.
     pub fn listen_for_outputs<F: FnMut(WlOutput, &OutputInfo, DispatchData) + 'static>(
         &self,
@@ -78,7 +78,7 @@ impl PrimarySelectionHandler {
 ///
 /// You need to implement this trait for your environment struct, by delegating it
 /// to its `PrimarySelectionHandler` field in order to get the associated methods
-/// on your [`Environment`](../environment/struct.environment.html).
+/// on your [`Environment`](../environment/struct.Environment.html).
 pub trait PrimarySelectionHandling {
     /// Access the primary selection associated with a seat.
     ///
@@ -14,7 +14,11 @@
 
 #[cfg(feature = "calloop")]
 use std::time::Duration;
-use std::{cell::RefCell, os::unix::io::RawFd, rc::Rc};
+use std::{
+    cell::RefCell,
+    os::unix::io::RawFd,
+    rc::{Rc, Weak},
+};
 
 use byteorder::{ByteOrder, NativeEndian};
 
@@ -25,11 +29,11 @@ use wayland_client::{
 };
 
 mod ffi;
-mod state;
 pub mod keysyms;
+mod state;
 
 use self::state::KbState;
-pub use self::state::{ModifiersState, RMLVO};
+pub use self::state::{LockState, ModifiersState, RMLVO};
 
 /// Possible kinds of key repetition
 pub enum RepeatKind {
@@ -82,6 +86,14 @@ pub enum Event<'a> {
         /// current state of the modifiers
         modifiers: ModifiersState,
     },
+    /// The set of locked modifiers has changed
+    ///
+    /// This is sent after the `Modifiers` event that changed the locks, and only if
+    /// the caps lock or num lock state actually changed.
+    LockStateChanged {
+        /// current state of the locks
+        locks: LockState,
+    },
     /// A key event occurred
     Key {
         /// serial number of the event
@@ -145,6 +157,8 @@ where
             .unwrap_or_else(KbState::new)?,
     ));
 
+    register_state(&keyboard, &state);
+
     let callback = Rc::new(RefCell::new(callback));
 
     // prepare the handler
@@ -198,6 +212,8 @@ where
             .unwrap_or_else(KbState::new)?,
     ));
 
+    register_state(&keyboard, &state);
+
     let callback = Rc::new(RefCell::new(callback));
 
     let repeat = match repeatkind {
@@ -254,6 +270,93 @@ where
     Ok((keyboard.detach(), source))
 }
 
+thread_local! {
+    // The states of the keyboards mapped by SCTK. The keyboards are owned by the user, so
+    // their user data is left to them.
+    static KEYBOARD_STATES: RefCell<Vec<(wl_keyboard::WlKeyboard, Weak<RefCell<KbState>>)>> =
+        RefCell::new(Vec::new());
+}
+
+// Forget about the keyboards that were released, or whose state was dropped
+fn prune_states(states: &mut Vec<(wl_keyboard::WlKeyboard, Weak<RefCell<KbState>>)>) {
+    states.retain(|(kbd, state)| kbd.as_ref().is_alive() && state.upgrade().is_some());
+}
+
+fn register_state(keyboard: &wl_keyboard::WlKeyboard, state: &Rc<RefCell<KbState>>) {
+    KEYBOARD_STATES.with(|states| {
+        let mut states = states.borrow_mut();
+        prune_states(&mut states);
+        states.push((keyboard.clone(), Rc::downgrade(state)));
+    })
+}
+
+fn get_state(keyboard: &wl_keyboard::WlKeyboard) -> Option<Rc<RefCell<KbState>>> {
+    KEYBOARD_STATES.with(|states| {
+        let mut states = states.borrow_mut();
+        prune_states(&mut states);
+        states
+            .iter()
+            .find(|(kbd, _)| kbd.as_ref().equals(keyboard.as_ref()))
+            .and_then(|(_, state)| state.upgrade())
+    })
+}
+
+/// Get the label of a key in the current keymap
+///
+/// This is the text produced by this key (given as its raw value) in the active layout
+/// without any modifier, for example `"ы"` for the `S` key of a russian layout. Keys that do
+/// not produce text, like `F1`, are labelled by their keysym name instead.
+///
+/// It is suitable to display the keyboard shortcuts of your app, and follows the changes of
+/// keymap and layout of the keyboard.
+///
+/// Returns `None` if the keyboard has not been mapped with
+/// [`map_keyboard`](fn.map_keyboard.html), if its keymap is not yet known, or if the key does
+/// not exist in the keymap.
+pub fn key_label(keyboard: &wl_keyboard::WlKeyboard, rawkey: u32) -> Option<String> {
+    let state = get_state(keyboard)?;
+    let state = state.borrow();
+    state.key_label(rawkey)
+}
+
+/// List the keys producing a given keysym in the current keymap
+///
+/// The returned values are the raw values of the keys, as given in the `rawkey` fields of
+/// the keyboard events. All levels of the active layout of each key are considered, so this
+/// can be used to detect shortcuts conflicting with each other.
+///
+/// Returns an empty list if the keyboard has not been mapped with
+/// [`map_keyboard`](fn.map_keyboard.html) or if its keymap is not yet known.
+pub fn keycodes_for_keysym(keyboard: &wl_keyboard::WlKeyboard, keysym: u32) -> Vec<u32> {
+    match get_state(keyboard) {
+        Some(state) => state.borrow().keycodes_for_keysym(keysym),
+        None => Vec::new(),
+    }
+}
+
+/// Get the current state of the locks of a keyboard
+///
+/// Returns `None` if the keyboard has not been mapped with
+/// [`map_keyboard`](fn.map_keyboard.html) or if its keymap is not yet known.
+pub fn lock_state(keyboard: &wl_keyboard::WlKeyboard) -> Option<LockState> {
+    let state = get_state(keyboard)?;
+    let state = state.borrow();
+    if state.ready() {
+        Some(state.lock_state())
+    } else {
+        None
+    }
+}
+
+/// Get the name of a keysym
+///
+/// For example `"Return"` for [`keysyms::XKB_KEY_Return`](keysyms/constant.XKB_KEY_Return.html).
+///
+/// Returns `None` if the keysym is invalid or if `libxkbcommon` is not available.
+pub fn keysym_name(keysym: u32) -> Option<String> {
+    state::keysym_name(keysym)
+}
+
 /*
  * Classic handling
  */
@@ -391,12 +494,14 @@ impl KbdHandler {
         keys: Vec<u8>,
         dispatch_data: wayland_client::DispatchData,
     ) {
-        let mut state = self.state.borrow_mut();
         let rawkeys = keys
             .chunks_exact(4)
             .map(NativeEndian::read_u32)
             .collect::<Vec<_>>();
-        let keys: Vec<u32> = rawkeys.iter().map(|k| state.get_one_sym_raw(*k)).collect();
+        let keys: Vec<u32> = {
+            let mut state = self.state.borrow_mut();
+            rawkeys.iter().map(|k| state.get_one_sym_raw(*k)).collect()
+        };
         (&mut *self.callback.borrow_mut())(
             Event::Enter {
                 serial,
@@ -506,18 +611,29 @@ impl KbdHandler {
         mods_latched: u32,
         mods_locked: u32,
         group: u32,
-        dispatch_data: wayland_client::DispatchData,
+        mut dispatch_data: wayland_client::DispatchData,
     ) {
-        {
+        let (modifiers, locks) = {
             let mut state = self.state.borrow_mut();
-            state.update_modifiers(mods_depressed, mods_latched, mods_locked, group);
-            (&mut *self.callback.borrow_mut())(
-                Event::Modifiers {
-                    modifiers: state.mods_state(),
+            let locks_changed =
+                state.update_modifiers(mods_depressed, mods_latched, mods_locked, group);
+            (
+                state.mods_state(),
+                if locks_changed {
+                    Some(state.lock_state())
+                } else {
+                    None
                 },
-                object,
-                dispatch_data,
-            );
+            )
+        };
+        let mut callback = self.callback.borrow_mut();
+        (*callback)(
+            Event::Modifiers { modifiers },
+            object.clone(),
+            dispatch_data.reborrow(),
+        );
+        if let Some(locks) = locks {
+            (*callback)(Event::LockStateChanged { locks }, object, dispatch_data);
         }
     }
 
@@ -587,9 +703,14 @@ impl calloop::EventSource for RepeatSource {
             .process_events(readiness, token, |(), timer_handle| {
                 if let Some(ref mut data) = *current_repeat.borrow_mut() {
                     // there is something to repeat
-                    let mut state = state.borrow_mut();
-                    let keysym = state.get_one_sym_raw(data.keycode);
-                    let utf8 = state.get_utf8_raw(data.keycode);
+                    // release the state before the callback, which may query it
+                    let (keysym, utf8) = {
+                        let mut state = state.borrow_mut();
+                        (
+                            state.get_one_sym_raw(data.keycode),
+                            state.get_utf8_raw(data.keycode),
+                        )
+                    };
                     let new_time = data.gap + data.time;
                     // notify the callback
                     callback(
@@ -22,6 +22,7 @@ pub(crate) struct KbState {
     xkb_compose_table: *mut ffi::xkb_compose_table,
     xkb_compose_state: *mut ffi::xkb_compose_state,
     mods_state: ModifiersState,
+    lock_state: LockState,
     locked: bool,
 }
 
@@ -69,6 +70,40 @@ pub struct ModifiersState {
     pub num_lock: bool,
 }
 
+/// Represents the current state of the keyboard locks
+///
+/// Each field is `true` if the lock is currently engaged, and `None` if the
+/// current keymap does not define the associated modifier.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LockState {
+    /// The "Caps lock" lock
+    pub caps_lock: Option<bool>,
+    /// The "Num lock" lock
+    pub num_lock: Option<bool>,
+}
+
+impl LockState {
+    fn from_state(state: *mut ffi::xkb_state) -> LockState {
+        let is_locked = |name: &[u8]| {
+            match unsafe {
+                (XKBH.xkb_state_mod_name_is_active)(
+                    state,
+                    name.as_ptr() as *const c_char,
+                    xkb_state_component::XKB_STATE_MODS_LOCKED,
+                )
+            } {
+                // the modifier does not exist in this keymap
+                -1 => None,
+                v => Some(v > 0),
+            }
+        };
+        LockState {
+            caps_lock: is_locked(ffi::XKB_MOD_NAME_CAPS),
+            num_lock: is_locked(ffi::XKB_MOD_NAME_NUM),
+        }
+    }
+}
+
 impl ModifiersState {
     fn new() -> ModifiersState {
         ModifiersState::default()
@@ -127,9 +162,9 @@ impl KbState {
         mods_latched: u32,
         mods_locked: u32,
         group: u32,
-    ) {
+    ) -> bool {
         if !self.ready() {
-            return;
+            return false;
         }
         let mask = unsafe {
             (XKBH.xkb_state_update_mask)(
@@ -146,6 +181,14 @@ impl KbState {
             // effective value of mods have changed, we need to update our state
             self.mods_state.update_with(self.xkb_state);
         }
+        // report whether the set of locked modifiers has changed
+        let lock_state = LockState::from_state(self.xkb_state);
+        if lock_state != self.lock_state {
+            self.lock_state = lock_state;
+            true
+        } else {
+            false
+        }
     }
 
     pub(crate) fn get_one_sym_raw(&mut self, keycode: u32) -> u32 {
@@ -238,6 +281,7 @@ impl KbState {
             xkb_compose_table: ptr::null_mut(),
             xkb_compose_state: ptr::null_mut(),
             mods_state: ModifiersState::new(),
+            lock_state: LockState::default(),
             locked: false,
         };
 
@@ -319,6 +363,8 @@ impl KbState {
         self.xkb_keymap = xkb_keymap;
         self.xkb_state = xkb_state;
         self.mods_state.update_with(xkb_state);
+        // the locks of a new keymap are its initial state, not a change to report
+        self.lock_state = LockState::from_state(xkb_state);
     }
 
     pub(crate) unsafe fn de_init(&mut self) {
@@ -326,6 +372,7 @@ impl KbState {
         self.xkb_state = ptr::null_mut();
         (XKBH.xkb_keymap_unref)(self.xkb_keymap);
         self.xkb_keymap = ptr::null_mut();
+        self.lock_state = LockState::default();
     }
 
     pub(crate) unsafe fn init_with_fd(&mut self, fd: RawFd, size: usize) {
@@ -367,6 +414,66 @@ impl KbState {
         Ok(())
     }
 
+    pub(crate) fn key_label(&self, keycode: u32) -> Option<String> {
+        if !self.ready() {
+            return None;
+        }
+        let sym = unsafe {
+            let layout = (XKBH.xkb_state_key_get_layout)(self.xkb_state, keycode + 8);
+            if layout == ffi::XKB_LAYOUT_INVALID {
+                return None;
+            }
+            let mut syms = ptr::null();
+            let count = (XKBH.xkb_keymap_key_get_syms_by_level)(
+                self.xkb_keymap,
+                keycode + 8,
+                layout,
+                0,
+                &mut syms,
+            );
+            if count < 1 {
+                return None;
+            }
+            *syms
+        };
+        // keys without a textual representation (like F1) are labelled by their keysym name
+        keysym_to_utf8(sym).or_else(|| keysym_name(sym))
+    }
+
+    pub(crate) fn keycodes_for_keysym(&self, keysym: u32) -> Vec<u32> {
+        let mut keycodes = Vec::new();
+        if !self.ready() {
+            return keycodes;
+        }
+        unsafe {
+            let min = (XKBH.xkb_keymap_min_keycode)(self.xkb_keymap);
+            let max = (XKBH.xkb_keymap_max_keycode)(self.xkb_keymap);
+            for key in min..=max {
+                let layout = (XKBH.xkb_state_key_get_layout)(self.xkb_state, key);
+                if layout == ffi::XKB_LAYOUT_INVALID {
+                    continue;
+                }
+                let levels = (XKBH.xkb_keymap_num_levels_for_key)(self.xkb_keymap, key, layout);
+                let found = (0..levels).any(|level| {
+                    let mut syms = ptr::null();
+                    let count = (XKBH.xkb_keymap_key_get_syms_by_level)(
+                        self.xkb_keymap,
+                        key,
+                        layout,
+                        level,
+                        &mut syms,
+                    );
+                    count > 0 && std::slice::from_raw_parts(syms, count as usize).contains(&keysym)
+                });
+                // xkb keycodes are offset by 8 from the ones of the wayland protocol
+                if found && key >= 8 {
+                    keycodes.push(key - 8);
+                }
+            }
+        }
+        keycodes
+    }
+
     pub(crate) unsafe fn key_repeats(&mut self, xkb_keycode_t: ffi::xkb_keycode_t) -> bool {
         (XKBH.xkb_keymap_key_repeats)(self.xkb_keymap, xkb_keycode_t) == 1
     }
@@ -385,6 +492,38 @@ impl KbState {
     pub(crate) fn mods_state(&self) -> ModifiersState {
         self.mods_state
     }
+
+    #[inline]
+    pub(crate) fn lock_state(&self) -> LockState {
+        self.lock_state
+    }
+}
+
+pub(crate) fn keysym_to_utf8(keysym: u32) -> Option<String> {
+    let xkbh = ffi::XKBCOMMON_OPTION.as_ref()?;
+    // an UTF-8 encoded char is at most 4 bytes long, plus the final `\0`
+    let mut buffer = vec![0u8; 8];
+    let size =
+        unsafe { (xkbh.xkb_keysym_to_utf8)(keysym, buffer.as_mut_ptr() as *mut _, buffer.len()) };
+    if size <= 1 {
+        return None;
+    }
+    // remove the final `\0`
+    buffer.truncate(size as usize - 1);
+    String::from_utf8(buffer).ok()
+}
+
+pub(crate) fn keysym_name(keysym: u32) -> Option<String> {
+    let xkbh = ffi::XKBCOMMON_OPTION.as_ref()?;
+    let mut buffer = vec![0u8; 64];
+    let size =
+        unsafe { (xkbh.xkb_keysym_get_name)(keysym, buffer.as_mut_ptr() as *mut _, buffer.len()) };
+    if size <= 0 {
+        return None;
+    }
+    // the returned size does not include the final `\0`
+    buffer.truncate(std::cmp::min(size as usize, buffer.len() - 1));
+    String::from_utf8(buffer).ok()
 }
 
 impl Drop for KbState {
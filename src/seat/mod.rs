@@ -10,7 +10,7 @@
 //! [`Environment::listen_for_seats`](../environment/struct.Environment.html) method.
 //!
 //! **Note:** if you don't use the [`default_environment!`](../macro.default_environment.html),
-//! you'll need to implement the [`SeatHandling`](trait.SeatHandling.hmtl) on your
+//! you'll need to implement the [`SeatHandling`](trait.SeatHandling.html) on your
 //! environment struct to access the added methods on
 //! [`Environment`](../environment/struct.Environment.html).
 
@@ -34,7 +34,11 @@ type SeatCallback = dyn FnMut(Attached<wl_seat::WlSeat>, &SeatData, DispatchData
 pub struct SeatData {
     /// The name of this seat
     ///
-    /// It can be used as an identifier for the seat
+    /// It can be used as an identifier for the seat, see
+    /// [`Environment::get_seat_by_name`](../environment/struct.Environment.html#method.get_seat_by_name).
+    ///
+    /// Seats of version 1 never receive a name from the compositor, in which case
+    /// this field is left empty.
     pub name: String,
     /// Whether this seat has a pointer available
     pub has_pointer: bool,
@@ -102,9 +106,9 @@ impl crate::environment::MultiGlobalHandler<wl_seat::WlSeat> for SeatHandler {
         // Seat is supported up to version 6
         let version = std::cmp::min(version, 6);
         let seat = registry.bind::<wl_seat::WlSeat>(version, id);
-        seat.as_ref()
-            .user_data()
-            .set_threadsafe(|| Mutex::new(SeatData::new()));
+        crate::user_data::init_user_data_map(seat.as_ref())
+            .expect("SCTK: newly created wl_seat already has UserData")
+            .insert_if_missing_threadsafe(|| Mutex::new(SeatData::new()));
         let cb_listeners = self.listeners.clone();
         seat.quick_assign(move |seat, event, ddata| {
             process_seat_event(seat, event, &cb_listeners, ddata)
@@ -118,7 +122,7 @@ impl crate::environment::MultiGlobalHandler<wl_seat::WlSeat> for SeatHandler {
                 true
             } else {
                 // This data must be `Mutex<SeatData>` if this seat is in our vec
-                let data = seat.as_ref().user_data().get::<Mutex<SeatData>>().unwrap();
+                let data = crate::user_data::get::<_, Mutex<SeatData>>(seat.as_ref()).unwrap();
                 let mut guard = data.lock().unwrap();
                 guard.defunct = true;
                 // notify the listeners that the seat is dead
@@ -137,6 +141,9 @@ impl crate::environment::MultiGlobalHandler<wl_seat::WlSeat> for SeatHandler {
     fn get_all(&self) -> Vec<Attached<wl_seat::WlSeat>> {
         self.seats.iter().map(|(_, s)| s.clone()).collect()
     }
+    fn get_all_with_ids(&self) -> Vec<(u32, Attached<wl_seat::WlSeat>)> {
+        self.seats.clone()
+    }
 }
 
 fn process_seat_event(
@@ -146,7 +153,7 @@ fn process_seat_event(
     mut ddata: DispatchData,
 ) {
     let new_data = {
-        let data = seat.as_ref().user_data().get::<Mutex<SeatData>>().unwrap();
+        let data = crate::user_data::get::<_, Mutex<SeatData>>(seat.as_ref()).unwrap();
         let mut guard = data.lock().unwrap();
         match event {
             wl_seat::Event::Name { name } => guard.name = name,
@@ -159,8 +166,8 @@ fn process_seat_event(
         }
         guard.clone()
     };
-    // only advertize a seat once it has a name
-    if !new_data.name.is_empty() {
+    // only advertize a seat once it has a name, unless it is too old to ever receive one
+    if !new_data.name.is_empty() || seat.as_ref().version() < 2 {
         listeners.borrow_mut().retain(|lst| {
             if let Some(cb) = Weak::upgrade(lst) {
                 (&mut *cb.borrow_mut())((*seat).clone(), &new_data, ddata.reborrow());
@@ -180,7 +187,7 @@ fn process_seat_event(
 /// will be set to `true`. This handler will not automatically detroy the output by calling its
 /// `release` method, to avoid interfering with your logic.
 pub fn clone_seat_data(seat: &wl_seat::WlSeat) -> Option<SeatData> {
-    if let Some(ref udata_mutex) = seat.as_ref().user_data().get::<Mutex<SeatData>>() {
+    if let Some(ref udata_mutex) = crate::user_data::get::<_, Mutex<SeatData>>(seat.as_ref()) {
         let udata = udata_mutex.lock().unwrap();
         Some(udata.clone())
     } else {
@@ -199,7 +206,7 @@ pub fn clone_seat_data(seat: &wl_seat::WlSeat) -> Option<SeatData> {
 /// will be set to `true`. This handler will not automatically detroy the output by calling its
 /// `release` method, to avoid interfering with your logic.
 pub fn with_seat_data<T, F: FnOnce(&SeatData) -> T>(seat: &wl_seat::WlSeat, f: F) -> Option<T> {
-    if let Some(ref udata_mutex) = seat.as_ref().user_data().get::<Mutex<SeatData>>() {
+    if let Some(ref udata_mutex) = crate::user_data::get::<_, Mutex<SeatData>>(seat.as_ref()) {
         let udata = udata_mutex.lock().unwrap();
         Some(f(&*udata))
     } else {
@@ -242,7 +249,7 @@ impl<E: SeatHandling> crate::environment::Environment<E> {
     /// For you to be notified of them as well, you need to first process them manually by calling
     /// `.get_all_seats()`.
     ///
-    /// The returned [`SeatListener`](../seat/struct.SeatListener.hmtl) keeps your callback alive,
+    /// The returned [`SeatListener`](../seat/struct.SeatListener.html) keeps your callback alive,
     /// dropping it will disable it.
     pub fn listen_for_seats<
         F: FnMut(Attached<wl_seat::WlSeat>, &SeatData, DispatchData) + 'static,
@@ -263,4 +270,14 @@ impl<E: crate::environment::MultiGlobalHandler<wl_seat::WlSeat>>
             .into_iter()
             .collect()
     }
+
+    /// Retrieve a seat by its name
+    ///
+    /// Returns `None` if no seat with this name currently exists. Seats that have not
+    /// yet received their name from the compositor cannot be found this way.
+    pub fn get_seat_by_name(&self, name: &str) -> Option<Attached<wl_seat::WlSeat>> {
+        self.get_all_seats().into_iter().find(|seat| {
+            with_seat_data(seat, |data| !data.defunct && data.name == name).unwrap_or(false)
+        })
+    }
 }
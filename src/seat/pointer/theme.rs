@@ -30,6 +30,8 @@ pub enum ThemeSpec<'a> {
     System,
 }
 
+type ThemedPointerRef = (wl_pointer::WlPointer, Weak<RefCell<PointerInner>>);
+
 /// Wrapper managing a system theme for pointer images
 ///
 /// You can use it to initialize new pointers in order
@@ -42,6 +44,7 @@ pub enum ThemeSpec<'a> {
 pub struct ThemeManager {
     themes: Rc<RefCell<ScaledThemeList>>,
     compositor: Attached<wl_compositor::WlCompositor>,
+    pointers: Rc<RefCell<Vec<ThemedPointerRef>>>,
 }
 
 impl ThemeManager {
@@ -58,9 +61,38 @@ impl ThemeManager {
         ThemeManager {
             compositor,
             themes: Rc::new(RefCell::new(ScaledThemeList::new(theme, shm))),
+            pointers: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
+    /// Change the theme used for pointer images
+    ///
+    /// The new theme is loaded, and the images of all the pointers themed by this
+    /// `ThemeManager` are updated to it. The previous theme is released.
+    ///
+    /// For example use this when the user changes their cursor theme or size in their
+    /// system settings: reading these settings is up to you.
+    pub fn set_theme(&self, theme: ThemeSpec) {
+        let new_themes = {
+            let themes = self.themes.borrow();
+            ScaledThemeList::new(theme, themes.shm.clone())
+        };
+        let old_themes = std::mem::replace(&mut *self.themes.borrow_mut(), new_themes);
+        let mut pointers = self.pointers.borrow_mut();
+        pointers.retain(|(pointer, inner)| match Weak::upgrade(inner) {
+            Some(inner) if pointer.as_ref().is_alive() => {
+                // we can't handle errors here, so ignore it
+                // worst that can happen is the cursor not being updated
+                // until the next call to `set_cursor`
+                let _ = inner.borrow().update_cursor(pointer);
+                true
+            }
+            _ => false,
+        });
+        // only release the old theme once the cursor surfaces use the buffers of the new one
+        drop(old_themes);
+    }
+
     /// Wrap a pointer to theme it
     pub fn theme_pointer(&self, pointer: wl_pointer::WlPointer) -> ThemedPointer {
         let surface = self.compositor.create_surface();
@@ -70,6 +102,7 @@ impl ThemeManager {
             last_serial: 0,
             current_cursor: "left_ptr".into(),
             scale_factor: 1,
+            hidden: false,
         }));
         let my_pointer = pointer.clone();
         let winner = Rc::downgrade(&inner);
@@ -86,6 +119,7 @@ impl ThemeManager {
                 }
             }),
         );
+        self.track_pointer(pointer.clone(), &inner);
         ThemedPointer { pointer, inner }
     }
 
@@ -109,6 +143,7 @@ impl ThemeManager {
             last_serial: 0,
             current_cursor: "left_ptr".into(),
             scale_factor: 1,
+            hidden: false,
         }));
 
         let inner2 = inner.clone();
@@ -139,12 +174,22 @@ impl ThemeManager {
                 }
             }),
         );
+        self.track_pointer(pointer.detach(), &inner);
 
         ThemedPointer {
             pointer: pointer.detach(),
             inner,
         }
     }
+
+    // Register a pointer for `set_theme`, forgetting about the dropped ones
+    fn track_pointer(&self, pointer: wl_pointer::WlPointer, inner: &Rc<RefCell<PointerInner>>) {
+        let mut pointers = self.pointers.borrow_mut();
+        pointers.retain(|(pointer, inner)| {
+            pointer.as_ref().is_alive() && Weak::upgrade(inner).is_some()
+        });
+        pointers.push((pointer, Rc::downgrade(inner)));
+    }
 }
 
 struct ScaledThemeList {
@@ -196,10 +241,15 @@ struct PointerInner {
     current_cursor: String,
     last_serial: u32,
     scale_factor: i32,
+    hidden: bool,
 }
 
 impl PointerInner {
     fn update_cursor(&self, pointer: &wl_pointer::WlPointer) -> Result<(), ()> {
+        if self.hidden {
+            pointer.set_cursor(self.last_serial, None, 0, 0);
+            return Ok(());
+        }
         let mut themes = self.themes.borrow_mut();
         let scale = self.scale_factor as u32;
         let cursor = themes.get_cursor(&self.current_cursor, scale).ok_or(())?;
@@ -257,6 +307,32 @@ impl ThemedPointer {
         inner.current_cursor = name.into();
         inner.update_cursor(&self.pointer)
     }
+
+    /// Hide or show the cursor
+    ///
+    /// While the cursor is hidden, calls to `set_cursor` still change the cursor
+    /// that'll be displayed once it is shown again, but keep it hidden.
+    ///
+    /// If this is done as an answer to an input event, you need to provide
+    /// the associated serial otherwise the server may ignore the request.
+    /// Otherwise the serial of the last `set_cursor` call is reused.
+    ///
+    /// When showing it again, the cursor given to the last `set_cursor`
+    /// call is restored.
+    pub fn set_hidden(&self, hidden: bool, serial: Option<u32>) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(s) = serial {
+            inner.last_serial = s;
+        }
+        inner.hidden = hidden;
+        // an unavailable cursor name was already reported by `set_cursor`
+        let _ = inner.update_cursor(&self.pointer);
+    }
+
+    /// Whether the cursor is currently hidden
+    pub fn is_hidden(&self) -> bool {
+        self.inner.borrow().hidden
+    }
 }
 
 impl Clone for ThemedPointer {
@@ -16,7 +16,7 @@ use wayland_client::{
 
 pub use wayland_protocols::xdg_shell::client::xdg_toplevel::State;
 use wayland_protocols::{
-    unstable::xdg_shell::v6::client::zxdg_shell_v6,
+    unstable::xdg_shell::v6::client::{zxdg_shell_v6, zxdg_toplevel_v6},
     xdg_shell::client::{xdg_toplevel, xdg_wm_base},
 };
 
@@ -129,12 +129,26 @@ pub trait ShellSurface: Send + Sync {
     fn set_min_size(&self, size: Option<(i32, i32)>);
     /// Set maximum surface size
     fn set_max_size(&self, size: Option<(i32, i32)>);
+    /// Set the parent of this shell surface, or unset it with `None`
+    ///
+    /// This is only supported with the `xdg_shell` and `zxdg_shell_v6` protocols,
+    /// and ignored by `wl_shell`. The default implementation does nothing.
+    fn set_parent(&self, parent: Option<&dyn ShellSurface>) {
+        let _ = parent;
+    }
     /// Retrive the `XdgToplevel` proxy if the underlying shell surface
     /// uses the `xdg_shell` protocol.
     ///
     /// This allows interactions with other protocol extensions, like
     /// `xdg_decoratins` for example.
     fn get_xdg(&self) -> Option<&xdg_toplevel::XdgToplevel>;
+    /// Retrive the `ZxdgToplevelV6` proxy if the underlying shell surface
+    /// uses the `zxdg_shell_v6` protocol.
+    ///
+    /// The default implementation returns `None`.
+    fn get_zxdg(&self) -> Option<&zxdg_toplevel_v6::ZxdgToplevelV6> {
+        None
+    }
 }
 
 struct ShellInner {
@@ -147,12 +161,12 @@ struct ShellInner {
 /// A handler for shells
 ///
 /// For use with the [`environment!`](../macro.environment.html) macro. It is already
-/// automatically included if you use the [`default_environment!`](../macro.default_environment.hmtl).
+/// automatically included if you use the [`default_environment!`](../macro.default_environment.html).
 ///
 /// To use it, you need to set it as a handler for the shells you want to support (`xdg_wm_base`,
 /// `zxdg_shell_v6` and/or `wl_shell`). You can then implement the
 /// [`ShellHandling`](trait.ShellHandling.html) by delegating it, to get the shell-related methods on
-/// [`Environment`](../environment/struct.environment.html)
+/// [`Environment`](../environment/struct.Environment.html)
 ///
 /// ```no_run
 /// # extern crate smithay_client_toolkit as sctk;
@@ -339,9 +353,9 @@ impl ShellHandling for ShellHandler {
 
 /// A helper trait for delegating shell handling
 ///
-/// If you don't use [`declare_default_environment!`](../macro.declare_default_environment.html) but still
+/// If you don't use [`default_environment!`](../macro.default_environment.html) but still
 /// want to use the shell helpers provided here, you need to implement this trait for your
-/// [`declare_environment!`](../macro.declare_environment.html)-generated type, by delegating it to one
+/// [`environment!`](../macro.environment.html)-generated type, by delegating it to one
 /// of the handlers you provided for the different shells.
 pub trait ShellHandling {
     /// Get the best available shell
@@ -108,6 +108,10 @@ impl ShellSurface for Wl {
         /* not available */
     }
 
+    fn set_parent(&self, _: Option<&dyn ShellSurface>) {
+        /* not available */
+    }
+
     fn get_xdg(&self) -> Option<&xdg_toplevel::XdgToplevel> {
         None
     }
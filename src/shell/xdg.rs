@@ -137,6 +137,10 @@ impl ShellSurface for Xdg {
         }
     }
 
+    fn set_parent(&self, parent: Option<&dyn ShellSurface>) {
+        self.toplevel.set_parent(parent.and_then(|p| p.get_xdg()));
+    }
+
     fn get_xdg(&self) -> Option<&xdg_toplevel::XdgToplevel> {
         Some(&self.toplevel)
     }
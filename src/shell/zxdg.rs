@@ -140,9 +140,17 @@ impl ShellSurface for Zxdg {
         }
     }
 
+    fn set_parent(&self, parent: Option<&dyn ShellSurface>) {
+        self.toplevel.set_parent(parent.and_then(|p| p.get_zxdg()));
+    }
+
     fn get_xdg(&self) -> Option<&xdg_toplevel::XdgToplevel> {
         None
     }
+
+    fn get_zxdg(&self) -> Option<&zxdg_toplevel_v6::ZxdgToplevelV6> {
+        Some(&self.toplevel)
+    }
 }
 
 impl Drop for Zxdg {
@@ -59,7 +59,7 @@ impl crate::environment::GlobalHandler<wl_shm::WlShm> for ShmHandler {
 ///
 /// You need to implement this trait for you environment struct, by
 /// delegating it to its `ShmHandler` field in order to get the
-/// associated methods on your [`Environment`](../environment/struct.environment.html).
+/// associated methods on your [`Environment`](../environment/struct.Environment.html).
 pub trait ShmHandling {
     /// Access the list of SHM formats supported by the compositor
     fn shm_formats(&self) -> Vec<wl_shm::Format>;
@@ -36,13 +36,11 @@ impl SurfaceUserData {
             my_callback.set(|| cb.clone());
         }
         let listener = add_output_listener(&output, move |output, info, ddata| {
-            let mut user_data = my_surface
-                .as_ref()
-                .user_data()
-                .get::<Mutex<SurfaceUserData>>()
-                .unwrap()
-                .lock()
-                .unwrap();
+            let mut user_data =
+                crate::user_data::get::<_, Mutex<SurfaceUserData>>(my_surface.as_ref())
+                    .unwrap()
+                    .lock()
+                    .unwrap();
             // update the scale factor of the relevant output
             for (ref o, ref mut factor, _) in user_data.outputs.iter_mut() {
                 if o.as_ref().equals(output.as_ref()) {
@@ -103,10 +101,7 @@ where
 {
     let callback = callback.map(|c| Rc::new(RefCell::new(c)));
     surface.quick_assign(move |surface, event, ddata| {
-        let mut user_data = surface
-            .as_ref()
-            .user_data()
-            .get::<Mutex<SurfaceUserData>>()
+        let mut user_data = crate::user_data::get::<_, Mutex<SurfaceUserData>>(surface.as_ref())
             .unwrap()
             .lock()
             .unwrap();
@@ -129,10 +124,9 @@ where
             }
         }
     });
-    surface
-        .as_ref()
-        .user_data()
-        .set_threadsafe(|| Mutex::new(SurfaceUserData::new()));
+    if let Some(map) = crate::user_data::init_user_data_map(surface.as_ref()) {
+        map.insert_if_missing_threadsafe(|| Mutex::new(SurfaceUserData::new()));
+    }
     surface.detach()
 }
 
@@ -171,10 +165,7 @@ impl<E: crate::environment::GlobalHandler<wl_compositor::WlCompositor>>
 /// Panics if the surface was not created using `Environment::create_surface` or
 /// `Environment::create_surface_with_dpi_callback`.
 pub fn get_surface_scale_factor(surface: &wl_surface::WlSurface) -> i32 {
-    surface
-        .as_ref()
-        .user_data()
-        .get::<Mutex<SurfaceUserData>>()
+    crate::user_data::get::<_, Mutex<SurfaceUserData>>(surface.as_ref())
         .expect("SCTK: Surface was not created by SCTK.")
         .lock()
         .unwrap()
@@ -186,10 +177,7 @@ pub fn get_surface_scale_factor(surface: &wl_surface::WlSurface) -> i32 {
 /// Panics if the surface was not created using `Environment::create_surface` or
 /// `Environment::create_surface_with_dpi_callback`.
 pub fn get_surface_outputs(surface: &wl_surface::WlSurface) -> Vec<wl_output::WlOutput> {
-    surface
-        .as_ref()
-        .user_data()
-        .get::<Mutex<SurfaceUserData>>()
+    crate::user_data::get::<_, Mutex<SurfaceUserData>>(surface.as_ref())
         .expect("SCTK: Surface was not created by SCTK.")
         .lock()
         .unwrap()
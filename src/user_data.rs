@@ -0,0 +1,62 @@
+//! Typed user data of the protocol objects created by SCTK
+//!
+//! The protocol objects SCTK creates and manages (the surfaces created by the
+//! [`Environment`](../environment/struct.Environment.html) and the outputs and seats tracked
+//! by its handlers) store their user data in a [`UserDataMap`](struct.UserDataMap.html). It can
+//! hold one value of each type, so you can attach your own state to these objects
+//! without conflicting with the one of SCTK.
+//!
+//! The keyboards returned by [`map_keyboard`](../seat/keyboard/fn.map_keyboard.html) and the
+//! pointers given to [`ThemeManager::theme_pointer_with_impl`](../seat/pointer/struct.ThemeManager.html)
+//! belong to you, SCTK does not touch their user data.
+//!
+//! SCTK only stores private types in these maps. Its state is accessed with the
+//! dedicated functions, like [`get_surface_scale_factor`](../fn.get_surface_scale_factor.html),
+//! [`with_output_info`](../output/fn.with_output_info.html) or
+//! [`with_seat_data`](../seat/fn.with_seat_data.html).
+//!
+//! ```no_run
+//! # use smithay_client_toolkit::{default_environment, init_default_environment};
+//! # use smithay_client_toolkit::user_data::user_data_map;
+//! # default_environment!(MyApp, desktop);
+//! # let (env, _display, _queue) = init_default_environment!(MyApp, desktop).unwrap();
+//! struct SurfaceRole(&'static str);
+//!
+//! let surface = env.create_surface();
+//! let map = user_data_map(&surface).unwrap();
+//! map.insert_if_missing(|| SurfaceRole("main window"));
+//! assert_eq!(map.get::<SurfaceRole>().unwrap().0, "main window");
+//! ```
+
+use wayland_client::{Interface, Proxy};
+
+pub use wayland_commons::user_data::UserDataMap;
+
+/// Access the user data map of a protocol object created by SCTK
+///
+/// Returns `None` if the object was not created by SCTK, or if its user data was
+/// already set to something else before SCTK got to manage it.
+pub fn user_data_map<I>(proxy: &I) -> Option<&UserDataMap>
+where
+    I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+{
+    proxy.as_ref().user_data().get::<UserDataMap>()
+}
+
+// Install a user data map on this object if it does not have any user data yet
+pub(crate) fn init_user_data_map<I>(proxy: &Proxy<I>) -> Option<&UserDataMap>
+where
+    I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+{
+    proxy.user_data().set_threadsafe(UserDataMap::new);
+    proxy.user_data().get::<UserDataMap>()
+}
+
+// Get the value of given type from the user data map of this object
+pub(crate) fn get<I, T>(proxy: &Proxy<I>) -> Option<&T>
+where
+    I: Interface + AsRef<Proxy<I>> + From<Proxy<I>>,
+    T: 'static,
+{
+    proxy.user_data().get::<UserDataMap>()?.get::<T>()
+}
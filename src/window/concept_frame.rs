@@ -15,7 +15,8 @@ use wayland_client::protocol::{
 use wayland_client::{Attached, DispatchData};
 
 use super::{
-    ARGBColor, ButtonColorSpec, ButtonState, ColorSpec, Frame, FrameRequest, State, WindowState,
+    ARGBColor, ButtonColorSpec, ButtonMode, ButtonState, ColorSpec, Frame, FrameButtons,
+    FrameRequest, State, WindowState,
 };
 use crate::seat::pointer::{ThemeManager, ThemeSpec, ThemedPointer};
 use crate::shm::DoubleMemPool;
@@ -147,6 +148,18 @@ enum UIButton {
     Close,
 }
 
+// Everything the content of the decorations depends on, used to skip
+// redrawing them when nothing changed since the last draw
+#[derive(Debug, Clone, PartialEq)]
+struct DrawState {
+    hidden: bool,
+    size: (u32, u32),
+    scales: Vec<u32>,
+    active: WindowState,
+    maximized: bool,
+    locations: Vec<Location>,
+}
+
 struct Part {
     surface: wl_surface::WlSurface,
     subsurface: wl_subsurface::WlSubsurface,
@@ -215,9 +228,19 @@ struct Inner {
     implem: Box<dyn FnMut(FrameRequest, u32, DispatchData)>,
     maximized: bool,
     buttons: (bool, bool, bool),
+    modes: FrameButtons,
 }
 
 impl Inner {
+    // Refresh which buttons are displayed from the config and the button modes
+    fn update_buttons(&mut self, config: &ConceptConfig) {
+        self.buttons = (
+            config.close_button.is_some() && self.modes.close != ButtonMode::Hidden,
+            config.maximize_button.is_some() && self.modes.maximize != ButtonMode::Hidden,
+            config.minimize_button.is_some() && self.modes.minimize != ButtonMode::Hidden,
+        );
+    }
+
     fn find_surface(&self, surface: &wl_surface::WlSurface) -> Location {
         if surface.as_ref().equals(&self.parts[HEAD].surface.as_ref()) {
             Location::Head
@@ -330,6 +353,7 @@ pub struct ConceptFrame {
     config: ConceptConfig,
     title: Option<String>,
     font_data: Option<Vec<u8>>,
+    last_drawn: Option<DrawState>,
 }
 
 impl Frame for ConceptFrame {
@@ -349,6 +373,7 @@ impl Frame for ConceptFrame {
             implem: implementation,
             maximized: false,
             buttons: (true, true, true),
+            modes: FrameButtons::default(),
         }));
 
         inner.borrow_mut().parts = vec![
@@ -381,6 +406,7 @@ impl Frame for ConceptFrame {
             config: ConceptConfig::default(),
             title: None,
             font_data: None,
+            last_drawn: None,
         })
     }
 
@@ -390,7 +416,8 @@ impl Frame for ConceptFrame {
         let pointer = self.themer.theme_pointer_with_impl(
             seat,
             move |event, pointer: ThemedPointer, ddata: DispatchData| {
-                let data: &RefCell<PointerUserData> = pointer.as_ref().user_data().get().unwrap();
+                let data: &RefCell<PointerUserData> =
+                    crate::user_data::get(pointer.as_ref()).unwrap();
                 let mut data = data.borrow_mut();
                 let mut inner = inner.borrow_mut();
                 match event {
@@ -459,6 +486,7 @@ impl Frame for ConceptFrame {
                                 &data.seat,
                                 inner.maximized,
                                 inner.resizable,
+                                inner.modes,
                             );
                             if let Some(req) = req {
                                 (&mut inner.implem)(req, serial, ddata);
@@ -469,23 +497,23 @@ impl Frame for ConceptFrame {
                 }
             },
         );
-        pointer.as_ref().user_data().set(|| {
-            RefCell::new(PointerUserData {
-                location: Location::None,
-                position: (0.0, 0.0),
-                seat: seat.detach(),
-            })
-        });
+        // this pointer belongs to the frame, which can use its user data
+        if let Some(map) = crate::user_data::init_user_data_map(pointer.as_ref()) {
+            map.insert_if_missing(|| {
+                RefCell::new(PointerUserData {
+                    location: Location::None,
+                    position: (0.0, 0.0),
+                    seat: seat.detach(),
+                })
+            });
+        }
         self.pointers.push(pointer);
     }
 
     fn remove_seat(&mut self, seat: &wl_seat::WlSeat) {
         self.pointers.retain(|pointer| {
-            let user_data = pointer
-                .as_ref()
-                .user_data()
-                .get::<RefCell<PointerUserData>>()
-                .unwrap();
+            let user_data =
+                crate::user_data::get::<_, RefCell<PointerUserData>>(pointer.as_ref()).unwrap();
             let guard = user_data.borrow_mut();
             if &guard.seat == seat {
                 pointer.release();
@@ -530,21 +558,50 @@ impl Frame for ConceptFrame {
     fn redraw(&mut self) {
         let inner = self.inner.borrow_mut();
 
+        let scales: Vec<u32> = inner
+            .parts
+            .iter()
+            .map(|part| crate::surface::get_surface_scale_factor(&part.surface) as u32)
+            .collect();
+
+        let locations = self
+            .pointers
+            .iter()
+            .flat_map(|p| {
+                if p.as_ref().is_alive() {
+                    let data: &RefCell<PointerUserData> =
+                        crate::user_data::get(p.as_ref()).unwrap();
+                    Some(data.borrow().location)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<Location>>();
+
+        let draw_state = DrawState {
+            hidden: self.hidden,
+            size: inner.size,
+            scales: scales.clone(),
+            active: self.active,
+            maximized: inner.maximized,
+            locations: locations.clone(),
+        };
+
+        // the surfaces still hold buffers with this exact content, nothing to do
+        if self.last_drawn.as_ref() == Some(&draw_state) {
+            return;
+        }
+
         if self.hidden {
             // don't draw the borders
             for p in &inner.parts {
                 p.surface.attach(None, 0, 0);
                 p.surface.commit();
             }
+            self.last_drawn = Some(draw_state);
             return;
         }
 
-        let scales: Vec<u32> = inner
-            .parts
-            .iter()
-            .map(|part| crate::surface::get_surface_scale_factor(&part.surface) as u32)
-            .collect();
-
         let (width, height) = inner.size;
 
         // Use header scale for all the thing.
@@ -600,22 +657,10 @@ impl Frame for ConceptFrame {
                         &mut header_canvas,
                         width,
                         header_scale,
-                        true,
                         self.active,
-                        &self
-                            .pointers
-                            .iter()
-                            .flat_map(|p| {
-                                if p.as_ref().is_alive() {
-                                    let data: &RefCell<PointerUserData> =
-                                        p.as_ref().user_data().get().unwrap();
-                                    Some(data.borrow().location)
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect::<Vec<Location>>(),
+                        &locations,
                         &self.config,
+                        inner.modes,
                     );
                     if let Some((ref font_face, font_size)) = self.config.title_font {
                         if let Some(title) = self.title.clone() {
@@ -658,16 +703,9 @@ impl Frame for ConceptFrame {
                                     title,
                                 );
 
-                                let mut button_count = 0isize;
-                                if self.config.close_button.is_some() {
-                                    button_count += 1;
-                                }
-                                if self.config.maximize_button.is_some() {
-                                    button_count += 1;
-                                }
-                                if self.config.minimize_button.is_some() {
-                                    button_count += 1;
-                                }
+                                let (close, maximize, minimize) = inner.buttons;
+                                let button_count =
+                                    close as isize + maximize as isize + minimize as isize;
 
                                 let scaled_button_size =
                                     HEADER_SIZE as isize * header_scale as isize;
@@ -855,6 +893,8 @@ impl Frame for ConceptFrame {
             }
             inner.parts[RIGHT].surface.commit();
         }
+
+        self.last_drawn = Some(draw_state);
     }
 
     fn subtract_borders(&self, width: i32, height: i32) -> (i32, i32) {
@@ -883,16 +923,22 @@ impl Frame for ConceptFrame {
 
     fn set_config(&mut self, config: ConceptConfig) {
         self.config = config;
-        let mut inner = self.inner.borrow_mut();
-        inner.buttons = (
-            self.config.close_button.is_some(),
-            self.config.maximize_button.is_some(),
-            self.config.minimize_button.is_some(),
-        );
+        self.last_drawn = None;
+        self.inner.borrow_mut().update_buttons(&self.config);
     }
 
     fn set_title(&mut self, title: String) {
         self.title = Some(title);
+        self.last_drawn = None;
+    }
+
+    fn set_buttons(&mut self, buttons: FrameButtons) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.modes != buttons {
+            inner.modes = buttons;
+            inner.update_buttons(&self.config);
+            self.last_drawn = None;
+        }
     }
 }
 
@@ -928,6 +974,7 @@ fn request_for_location(
     seat: &wl_seat::WlSeat,
     maximized: bool,
     resizable: bool,
+    modes: FrameButtons,
 ) -> Option<FrameRequest> {
     use wayland_protocols::xdg_shell::client::xdg_toplevel::ResizeEdge;
     match location {
@@ -950,15 +997,19 @@ fn request_for_location(
             Some(FrameRequest::Resize(seat.clone(), ResizeEdge::TopRight))
         }
         Location::Head => Some(FrameRequest::Move(seat.clone())),
-        Location::Button(UIButton::Close) => Some(FrameRequest::Close),
-        Location::Button(UIButton::Maximize) => {
+        Location::Button(UIButton::Close) if modes.close == ButtonMode::Enabled => {
+            Some(FrameRequest::Close)
+        }
+        Location::Button(UIButton::Maximize) if modes.maximize == ButtonMode::Enabled => {
             if maximized {
                 Some(FrameRequest::UnMaximize)
             } else {
                 Some(FrameRequest::Maximize)
             }
         }
-        Location::Button(UIButton::Minimize) => Some(FrameRequest::Minimize),
+        Location::Button(UIButton::Minimize) if modes.minimize == ButtonMode::Enabled => {
+            Some(FrameRequest::Minimize)
+        }
         _ => None,
     }
 }
@@ -986,10 +1037,10 @@ fn draw_buttons(
     canvas: &mut Canvas,
     width: u32,
     scale: u32,
-    maximizable: bool,
     state: WindowState,
     mouses: &[Location],
     config: &ConceptConfig,
+    modes: FrameButtons,
 ) {
     let scale = scale as usize;
 
@@ -1008,10 +1059,12 @@ fn draw_buttons(
 
     let mut drawn_buttons = 0usize;
 
-    if width >= HEADER_SIZE {
+    if width >= HEADER_SIZE && modes.close != ButtonMode::Hidden {
         if let Some((ref icon_config, ref btn_config)) = config.close_button {
             // Draw the close button
-            let btn_state = if mouses
+            let btn_state = if modes.close == ButtonMode::Disabled {
+                ButtonState::Disabled
+            } else if mouses
                 .iter()
                 .any(|&l| l == Location::Button(UIButton::Close))
             {
@@ -1035,9 +1088,11 @@ fn draw_buttons(
         }
     }
 
-    if width as usize >= (drawn_buttons + 1) * HEADER_SIZE as usize {
+    if width as usize >= (drawn_buttons + 1) * HEADER_SIZE as usize
+        && modes.maximize != ButtonMode::Hidden
+    {
         if let Some((ref icon_config, ref btn_config)) = config.maximize_button {
-            let btn_state = if !maximizable {
+            let btn_state = if modes.maximize == ButtonMode::Disabled {
                 ButtonState::Disabled
             } else if mouses
                 .iter()
@@ -1069,9 +1124,13 @@ fn draw_buttons(
         }
     }
 
-    if width as usize >= (drawn_buttons + 1) * HEADER_SIZE as usize {
+    if width as usize >= (drawn_buttons + 1) * HEADER_SIZE as usize
+        && modes.minimize != ButtonMode::Hidden
+    {
         if let Some((ref icon_config, ref btn_config)) = config.minimize_button {
-            let btn_state = if mouses
+            let btn_state = if modes.minimize == ButtonMode::Disabled {
+                ButtonState::Disabled
+            } else if mouses
                 .iter()
                 .any(|&l| l == Location::Button(UIButton::Minimize))
             {
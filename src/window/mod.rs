@@ -47,6 +47,38 @@ pub enum WindowState {
     Inactive,
 }
 
+/// Represents how a button of the decorations is displayed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ButtonMode {
+    /// The button is displayed and reacts to clicks
+    Enabled,
+    /// The button is displayed greyed and ignores clicks
+    Disabled,
+    /// The button is not displayed
+    Hidden,
+}
+
+/// The modes of the buttons of the decorations
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameButtons {
+    /// The "Close" button
+    pub close: ButtonMode,
+    /// The "Maximize" button
+    pub maximize: ButtonMode,
+    /// The "Minimize" button
+    pub minimize: ButtonMode,
+}
+
+impl Default for FrameButtons {
+    fn default() -> FrameButtons {
+        FrameButtons {
+            close: ButtonMode::Enabled,
+            maximize: ButtonMode::Enabled,
+            minimize: ButtonMode::Enabled,
+        }
+    }
+}
+
 impl From<bool> for WindowState {
     fn from(b: bool) -> WindowState {
         if b {
@@ -120,6 +152,29 @@ pub enum Decorations {
     None,
 }
 
+/// A snapshot of the state of a window
+///
+/// It can be saved when your app is closed and given back to
+/// [`Window::restore_state`](struct.Window.html#method.restore_state) to reopen
+/// your window as it was. If the `serde` cargo feature is enabled, it can be
+/// serialized and deserialized.
+///
+/// Wayland does not let clients know or choose the position of their windows,
+/// nor the name of the output a window is fullscreened on, so they are not part
+/// of the snapshot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowStateSnapshot {
+    /// The size of the window when it is neither maximized nor fullscreen
+    ///
+    /// This is the interior size, not counting decorations, in logical pixels.
+    pub logical_size: (u32, u32),
+    /// Whether the window is maximized
+    pub maximized: bool,
+    /// Whether the window is fullscreen
+    pub fullscreen: bool,
+}
+
 struct WindowInner<F> {
     frame: Arc<Mutex<F>>,
     shell_surface: Arc<Box<dyn shell::ShellSurface>>,
@@ -128,6 +183,9 @@ struct WindowInner<F> {
     max_size: Option<(u32, u32)>,
     current_size: (u32, u32),
     old_size: Option<(u32, u32)>,
+    fullscreen: bool,
+    // the size and maximization state of the window before it was made fullscreen
+    pre_fullscreen: Option<((u32, u32), bool)>,
     decorated: bool,
 }
 
@@ -239,6 +297,17 @@ impl<F: Frame + 'static> Window<F> {
                             });
                             // compute frame changes
                             let need_refresh = frame.set_states(&states);
+                            let fullscreen = states.contains(&State::Fullscreen);
+                            if fullscreen && !inner.fullscreen {
+                                // we are getting fullscreen, store the state for snapshots
+                                inner.pre_fullscreen = Some((
+                                    inner.old_size.unwrap_or(inner.current_size),
+                                    inner.old_size.is_some(),
+                                ));
+                            } else if !fullscreen {
+                                inner.pre_fullscreen = None;
+                            }
+                            inner.fullscreen = fullscreen;
                             // check if the maximization state changed
                             if states.contains(&State::Maximized) {
                                 if inner.old_size.is_none() {
@@ -311,6 +380,8 @@ impl<F: Frame + 'static> Window<F> {
             max_size: None,
             current_size: initial_dims,
             old_size: None,
+            fullscreen: false,
+            pre_fullscreen: None,
             decorated: true,
         });
 
@@ -383,6 +454,28 @@ impl<F: Frame + 'static> Window<F> {
         &self.surface
     }
 
+    /// Access the shell surface of this Window
+    ///
+    /// This gives access to the underlying shell protocol objects, for example
+    /// the `xdg_toplevel` via `get_xdg()`, for use with other protocol extensions.
+    ///
+    /// The `Window` handles the events of these objects and tracks the size constraints
+    /// it sets on them: changing these constraints directly will not be reflected by the
+    /// `Window`, prefer its own methods for this.
+    pub fn shell_surface(&self) -> &dyn shell::ShellSurface {
+        &**self.shell_surface
+    }
+
+    /// Access the server-side decoration object of this Window, if any
+    ///
+    /// It only exists if the compositor supports `xdg_decoration` and the window
+    /// uses the `xdg_shell` protocol. The `Window` handles its events to hide or show
+    /// its own decorations: use [`set_decorate`](#method.set_decorate) rather than
+    /// setting the decoration mode yourself, to keep them in sync.
+    pub fn decoration(&self) -> Option<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1> {
+        self.decoration.lock().unwrap().clone()
+    }
+
     /// Refreshes the frame
     ///
     /// Redraws the frame to match its requested state (dimensions, presence/
@@ -540,6 +633,20 @@ impl<F: Frame + 'static> Window<F> {
         self.shell_surface.set_minimized();
     }
 
+    /// Set the parent of this window, or unset it with `None`
+    ///
+    /// A window with a parent is typically a dialog: the compositor can for example
+    /// keep it above its parent, or center it on it. If the parent is destroyed, the
+    /// compositor makes this window a child of its grand-parent, or a top-level window
+    /// if it had none.
+    ///
+    /// This is only supported if the compositor uses the `xdg_shell` or `zxdg_shell_v6`
+    /// protocol.
+    pub fn set_parent<G: Frame>(&self, parent: Option<&Window<G>>) {
+        self.shell_surface
+            .set_parent(parent.map(|p| &**p.shell_surface as &dyn shell::ShellSurface));
+    }
+
     /// Request the window to be set fullscreen
     ///
     /// Note: you need to manually disable the decorations if you
@@ -589,6 +696,47 @@ impl<F: Frame + 'static> Window<F> {
         }
     }
 
+    /// Take a snapshot of the current state of this window
+    ///
+    /// If the window is maximized or fullscreen, the snapshot contains the size it had
+    /// before, and that it'll be given back when leaving these states.
+    pub fn snapshot_state(&self) -> WindowStateSnapshot {
+        let inner = self.inner.lock().unwrap();
+        let inner = inner
+            .as_ref()
+            .expect("SCTK: Window was not initialized before use.");
+        let (logical_size, maximized) = match inner.pre_fullscreen {
+            Some(state) => state,
+            None => (
+                inner.old_size.unwrap_or(inner.current_size),
+                inner.old_size.is_some(),
+            ),
+        };
+        WindowStateSnapshot {
+            logical_size,
+            maximized,
+            fullscreen: inner.fullscreen,
+        }
+    }
+
+    /// Restore a state previously saved with `snapshot_state`
+    ///
+    /// The window is resized to the saved size, and maximization and fullscreen
+    /// are requested from the compositor if needed. This is best called right after
+    /// creating the window, before drawing its contents for the first time: you should
+    /// then draw them at the saved size, unless the compositor sends you a `Configure`
+    /// event with another one.
+    pub fn restore_state(&mut self, state: &WindowStateSnapshot) {
+        let (w, h) = state.logical_size;
+        self.resize(w, h);
+        if state.maximized {
+            self.set_maximized();
+        }
+        if state.fullscreen {
+            self.set_fullscreen(None);
+        }
+    }
+
     /// Sets the frame configuration for the window
     ///
     /// This allows to configure the frame at runtime if it supports
@@ -597,6 +745,19 @@ impl<F: Frame + 'static> Window<F> {
     pub fn set_frame_config(&mut self, config: F::Config) {
         self.frame.lock().unwrap().set_config(config)
     }
+
+    /// Sets which buttons of the decorations are enabled, disabled or hidden
+    ///
+    /// Hidden buttons free up space for the title, and disabled ones are drawn
+    /// greyed and ignore clicks. Clicks on the close button are delivered as
+    /// `Event::Close`, the same way as close requests of the compositor, so
+    /// your app can ask for a confirmation before closing the window.
+    ///
+    /// You need to call `refresh()` afterwards for this to properly
+    /// take effect.
+    pub fn set_frame_buttons(&self, buttons: FrameButtons) {
+        self.frame.lock().unwrap().set_buttons(buttons)
+    }
 }
 
 impl<F: Frame> Drop for Window<F> {
@@ -688,6 +849,13 @@ pub trait Frame: Sized {
 
     /// Sets the frames title
     fn set_title(&mut self, title: String);
+
+    /// Sets the modes of the buttons of the frame
+    ///
+    /// Frames that do not draw buttons can ignore this.
+    ///
+    /// Calling this should *not* trigger a redraw
+    fn set_buttons(&mut self, _buttons: FrameButtons) {}
 }
 
 impl<E> Environment<E>